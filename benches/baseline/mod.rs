@@ -0,0 +1,232 @@
+// The tokenizer as it was before lexing moved onto the input string: the
+// whole input is collected into a `Vec<char>` and every string copied into
+// its token. Kept to the JSONC grammar the benchmark document uses, as the
+// baseline the current lexer is measured against.
+
+use tosqweel::Number;
+
+#[derive(Debug)]
+pub enum Token {
+    LeftBrace,
+    LeftBracket,
+    Number(Number),
+    RightBrace,
+    RightBracket,
+    String(String),
+    Colon,
+    Comma,
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    fn advance(&mut self, text: &[char]) {
+        for &c in text {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<(Token, Span)>, String> {
+    let data: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut span = Span { line: 1, column: 1 };
+    let mut rest = &data[..];
+
+    while !rest.is_empty() {
+        let start = rest;
+        let mut push = |node| tokens.push((node, span));
+
+        match rest[0] {
+            c if c.is_whitespace() => rest = skip_whitespace(rest),
+            '/' if rest.get(1) == Some(&'/') => rest = skip_to_delimeter(rest, '\n'),
+            '/' if rest.get(1) == Some(&'*') => {
+                rest = skip_to_delimeters(&rest[2..], '*', '/')
+                    .ok_or_else(|| "unterminated comment".to_string())?;
+            }
+            '{' => {
+                push(Token::LeftBrace);
+                rest = &rest[1..];
+            }
+            '}' => {
+                push(Token::RightBrace);
+                rest = &rest[1..];
+            }
+            '[' => {
+                push(Token::LeftBracket);
+                rest = &rest[1..];
+            }
+            ']' => {
+                push(Token::RightBracket);
+                rest = &rest[1..];
+            }
+            ':' => {
+                push(Token::Colon);
+                rest = &rest[1..];
+            }
+            ',' => {
+                push(Token::Comma);
+                rest = &rest[1..];
+            }
+            '"' => {
+                let (s, remaining) = tokenize_string(rest)?;
+                push(Token::String(s));
+                rest = remaining;
+            }
+            't' | 'f' => {
+                let (b, remaining) = tokenize_bool(rest)?;
+                push(Token::Bool(b));
+                rest = remaining;
+            }
+            'n' if rest.starts_with(&['n', 'u', 'l', 'l']) => {
+                push(Token::Null);
+                rest = &rest[4..];
+            }
+            '-' | '0'..='9' => {
+                let (num, remaining) = tokenize_number(rest)?;
+                push(Token::Number(num));
+                rest = remaining;
+            }
+            c => return Err(format!("unexpected character {c}")),
+        }
+
+        span.advance(&start[..start.len() - rest.len()]);
+    }
+
+    Ok(tokens)
+}
+
+fn skip_to_delimeters(data: &[char], first: char, second: char) -> Option<&[char]> {
+    let mut i = 0;
+    while i + 1 < data.len() {
+        if data[i] == first && data[i + 1] == second {
+            return Some(&data[i + 2..]);
+        }
+        i += 1;
+    }
+
+    None
+}
+
+fn skip_to_delimeter(data: &[char], delim: char) -> &[char] {
+    let mut i = 0;
+    while i < data.len() && data[i] != delim {
+        i += 1;
+    }
+    i += 1;
+    &data[i.min(data.len())..]
+}
+
+fn skip_whitespace(data: &[char]) -> &[char] {
+    let mut i = 0;
+    while i < data.len() && data[i].is_whitespace() {
+        i += 1;
+    }
+    &data[i..]
+}
+
+fn tokenize_string(data: &[char]) -> Result<(String, &[char]), String> {
+    let mut s = String::new();
+    let mut i = 1;
+
+    while i < data.len() && data[i] != '"' {
+        match data[i] {
+            '\\' => {
+                let (c, len) = tokenize_escape(&data[i..])?;
+                s.push(c);
+                i += len;
+            }
+            c if (c as u32) < 0x20 => return Err("control character in string".to_string()),
+            c => {
+                s.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if i >= data.len() {
+        return Err("unterminated string".to_string());
+    }
+
+    Ok((s, &data[i + 1..]))
+}
+
+fn tokenize_escape(data: &[char]) -> Result<(char, usize), String> {
+    let decoded = match data.get(1) {
+        Some('"') => '"',
+        Some('\\') => '\\',
+        Some('/') => '/',
+        Some('b') => '\u{8}',
+        Some('f') => '\u{c}',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('u') => {
+            let digits: String = data.iter().skip(2).take(4).collect();
+            let code = u32::from_str_radix(&digits, 16).map_err(|e| e.to_string())?;
+            let c = char::from_u32(code).ok_or_else(|| "surrogate escape".to_string())?;
+            return Ok((c, 6));
+        }
+        _ => return Err("invalid escape".to_string()),
+    };
+
+    Ok((decoded, 2))
+}
+
+fn tokenize_number(data: &[char]) -> Result<(Number, &[char]), String> {
+    let mut i = 0;
+    let mut is_float = false;
+    if data[i] == '-' {
+        i += 1;
+    }
+    i = skip_digits(data, i);
+    if data.get(i) == Some(&'.') {
+        is_float = true;
+        i = skip_digits(data, i + 1);
+    }
+    if matches!(data.get(i), Some('e' | 'E')) {
+        is_float = true;
+        i += 1;
+        if matches!(data.get(i), Some('+' | '-')) {
+            i += 1;
+        }
+        i = skip_digits(data, i);
+    }
+
+    let s: String = data[..i].iter().collect();
+    let num = if is_float {
+        Number::Float(s.parse().map_err(|_| format!("invalid number {s}"))?)
+    } else {
+        Number::Integer(s.parse().map_err(|_| format!("invalid number {s}"))?)
+    };
+
+    Ok((num, &data[i..]))
+}
+
+fn skip_digits(data: &[char], mut i: usize) -> usize {
+    while i < data.len() && data[i].is_ascii_digit() {
+        i += 1;
+    }
+    i
+}
+
+fn tokenize_bool(data: &[char]) -> Result<(bool, &[char]), String> {
+    if data.starts_with(&['t', 'r', 'u', 'e']) {
+        Ok((true, &data[4..]))
+    } else if data.starts_with(&['f', 'a', 'l', 's', 'e']) {
+        Ok((false, &data[5..]))
+    } else {
+        Err("invalid literal".to_string())
+    }
+}
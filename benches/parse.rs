@@ -0,0 +1,131 @@
+// Measures parsing throughput on a generated document, against the
+// `Vec<char>` tokenizer the lexer replaced
+//
+// Run with `cargo bench`, optionally passing the number of records.
+
+mod baseline;
+
+use std::{borrow::Cow, hint::black_box, time::Instant};
+
+use tosqweel::{
+    ParseOptions,
+    lexer::{Lexer, Token, tokenize},
+    parse_with,
+};
+
+const RUNS: usize = 5;
+
+fn document(records: usize) -> String {
+    let mut out = String::from("[\n");
+    for i in 0..records {
+        out += &format!(
+            "  {{\"id\": {i}, \"name\": \"user {i}\", \"score\": {}.5, \"active\": {}, \
+             \"bio\": \"line one\\nline \\\"two\\\" \\u00e9\", \"tags\": [\"a\", \"b\"], \
+             \"address\": {{\"street\": \"{i} Main St\", \"zip\": null}}}},  // row {i}\n",
+            i % 100,
+            i % 2 == 0
+        );
+    }
+    out += "]\n";
+    out
+}
+
+fn main() {
+    let records = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(200_000);
+    let input = document(records);
+    let options = ParseOptions::default();
+
+    let expected = baseline::tokenize(&input).expect("document tokenizes");
+    let tokens = tokenize(&input, &options).expect("document tokenizes");
+    assert!(
+        expected.len() == tokens.len()
+            && expected.iter().zip(&tokens).all(|((old, span), new)| {
+                (span.line, span.column) == (new.span.line, new.span.column)
+                    && same_token(old, &new.node)
+            }),
+        "the baseline tokenizes the document differently"
+    );
+    // Memory held besides the input string: the baseline's copy of it as
+    // characters and its token strings, against only the strings the lexer
+    // had to decode
+    let mebibytes = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    let copied: usize = expected
+        .iter()
+        .map(|(token, _)| match token {
+            baseline::Token::String(s) => s.len(),
+            _ => 0,
+        })
+        .sum();
+    let decoded: usize = tokens
+        .iter()
+        .map(|token| match &token.node {
+            Token::String(Cow::Owned(s)) => s.len(),
+            _ => 0,
+        })
+        .sum();
+    println!(
+        "tokenize memory: {:.1} MiB for the baseline, {:.1} MiB for the lexer",
+        mebibytes(input.chars().count() * size_of::<char>() + copied),
+        mebibytes(decoded)
+    );
+
+    let before = report("tokenize (Vec<char> baseline)", &input, || {
+        black_box(baseline::tokenize(black_box(&input)).expect("document tokenizes"));
+    });
+    let after = report("tokenize", &input, || {
+        black_box(tokenize(black_box(&input), &options).expect("document tokenizes"));
+    });
+    println!("tokenize: {:.1}x the baseline", before / after);
+    // Both tokenizers above spend much of their time building the token
+    // vector, the parser pulls tokens one at a time instead
+    report("lex without collecting", &input, || {
+        let tokens =
+            Lexer::new(black_box(&input), &options).map(|token| token.expect("document tokenizes"));
+        black_box(tokens.count());
+    });
+    report("parse", &input, || {
+        black_box(parse_with(black_box(&input), &options).expect("document parses"));
+    });
+}
+
+fn same_token(old: &baseline::Token, new: &Token<'_>) -> bool {
+    match (old, new) {
+        (baseline::Token::Number(a), Token::Number(b)) => a.to_string() == b.to_string(),
+        (baseline::Token::String(a), Token::String(b)) => a == b,
+        (baseline::Token::Bool(a), Token::Bool(b)) => a == b,
+        (baseline::Token::LeftBrace, Token::LeftBrace)
+        | (baseline::Token::LeftBracket, Token::LeftBracket)
+        | (baseline::Token::RightBrace, Token::RightBrace)
+        | (baseline::Token::RightBracket, Token::RightBracket)
+        | (baseline::Token::Colon, Token::Colon)
+        | (baseline::Token::Comma, Token::Comma)
+        | (baseline::Token::Null, Token::Null) => true,
+        _ => false,
+    }
+}
+
+// Prints the best of several runs as throughput over the input, returning
+// its time
+fn report(name: &str, input: &str, mut run: impl FnMut()) -> f64 {
+    let megabytes = input.len() as f64 / (1024.0 * 1024.0);
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+
+    println!(
+        "{}: {:.1} MiB in {:.3}s, {:.1} MiB/s (best of {})",
+        name,
+        megabytes,
+        best,
+        megabytes / best,
+        RUNS
+    );
+
+    best
+}
@@ -0,0 +1,340 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{Span, error::Error};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+// Characters quoted on either side of the reported column
+const CONTEXT: usize = 40;
+
+// An error together with the file and line it points into
+#[derive(Debug)]
+pub struct Report {
+    pub path: String,
+    // None when the input can no longer be read, as when streamed from stdin
+    pub line: Option<Excerpt>,
+    pub span: Span,
+    // Line of the secondary location named by `Error::note`
+    pub note_line: Option<Excerpt>,
+    pub error: Error,
+}
+
+// The quoted part of a source line. Long lines, such as minified documents,
+// are cut down to the characters around the reported column.
+#[derive(Debug)]
+pub struct Excerpt {
+    pub text: String,
+    // Column of the first quoted character
+    pub column: usize,
+    // Whether the line goes on past the quoted text
+    pub truncated: bool,
+}
+
+impl Excerpt {
+    pub fn new(line: &str, column: usize) -> Excerpt {
+        let skip = column.saturating_sub(1 + CONTEXT);
+        let mut chars = line.chars().skip(skip);
+        let text = chars
+            .by_ref()
+            .take(column.saturating_sub(1) - skip + CONTEXT)
+            .collect();
+        Excerpt {
+            text,
+            column: skip + 1,
+            truncated: chars.next().is_some(),
+        }
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+impl std::error::Error for Report {}
+
+impl Report {
+    // Looks up the quoted lines, None for errors without a location
+    pub fn new(error: Error, path: &str, source: &str) -> Option<Report> {
+        Report::with_lines(error, path, |span| {
+            let line = source.lines().nth(span.line - 1).unwrap_or("");
+            Some(Excerpt::new(line, span.column))
+        })
+    }
+
+    // Like `new` for input that was streamed rather than held in memory,
+    // reading the quoted text back from the file. Only the bytes around the
+    // location are read, however long its line.
+    pub fn from_file(error: Error, path: &str) -> Option<Report> {
+        Report::with_lines(error, path, |span| read_excerpt(path, span).ok())
+    }
+
+    // Points at the location without quoting it
+    pub fn unquoted(error: Error, path: &str) -> Option<Report> {
+        Report::with_lines(error, path, |_| None)
+    }
+
+    fn with_lines(
+        error: Error,
+        path: &str,
+        line_at: impl Fn(Span) -> Option<Excerpt>,
+    ) -> Option<Report> {
+        let span = error.span()?;
+        Some(Report {
+            path: path.to_string(),
+            line: line_at(span),
+            span,
+            note_line: error.note().and_then(|(note, _)| line_at(note)),
+            error,
+        })
+    }
+
+    // Renders the error the way rustc does, quoting the offending line with
+    // a caret under the column
+    pub fn render(&self, color: bool) -> String {
+        self.render_as("error", RED, color)
+    }
+
+    pub fn render_warning(&self, color: bool) -> String {
+        self.render_as("warning", YELLOW, color)
+    }
+
+    fn render_as(&self, level: &str, style: &str, color: bool) -> String {
+        let note = self.error.note();
+        let widest = note.map_or(self.span.line, |(span, _)| span.line.max(self.span.line));
+        let gutter = " ".repeat(widest.to_string().len());
+
+        let mut out = format!(
+            "{}{}\n",
+            paint(style, level, color),
+            paint(BOLD, &format!(": {}", self.error), color)
+        );
+        out += &self.snippet(&gutter, self.span, self.line.as_ref(), style, color);
+        if let Some((span, label)) = note {
+            out += &format!(
+                "\n{}{}\n",
+                paint(GREEN, "note", color),
+                paint(BOLD, &format!(": {label}"), color)
+            );
+            out += &self.snippet(&gutter, span, self.note_line.as_ref(), GREEN, color);
+        }
+        if let Some(help) = self.error.help() {
+            out += &format!(
+                "\n{} {} {}",
+                gutter,
+                paint(BLUE, "=", color),
+                paint(BOLD, &format!("help: {help}"), color)
+            );
+        }
+
+        out
+    }
+
+    // Location arrow followed by the quoted line and a caret under the column
+    fn snippet(
+        &self,
+        gutter: &str,
+        span: Span,
+        line: Option<&Excerpt>,
+        style: &str,
+        color: bool,
+    ) -> String {
+        let mut out = format!(
+            "{}{} {}:{}:{}",
+            gutter,
+            paint(BLUE, "-->", color),
+            self.path,
+            span.line,
+            span.column
+        );
+        let Some(line) = line else {
+            return out;
+        };
+
+        let line_number = span.line.to_string();
+        let padding = " ".repeat(gutter.len() - line_number.len());
+        // Cut ends are marked, and tabs repeated so the caret lines up with
+        // the quoted line
+        let (before, after) = (
+            if line.column > 1 { "..." } else { "" },
+            if line.truncated { "..." } else { "" },
+        );
+        let indent: String = before
+            .chars()
+            .chain(
+                line.text
+                    .chars()
+                    .take(span.column.saturating_sub(line.column)),
+            )
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        out += &format!("\n{} {}\n", gutter, paint(BLUE, "|", color));
+        out += &format!(
+            "{}{} {} {}{}{}\n",
+            paint(BLUE, &line_number, color),
+            padding,
+            paint(BLUE, "|", color),
+            before,
+            line.text,
+            after
+        );
+        out += &format!(
+            "{} {} {}{}",
+            gutter,
+            paint(BLUE, "|", color),
+            indent,
+            paint(style, "^", color)
+        );
+
+        out
+    }
+}
+
+// Reads enough bytes either side of the span for `CONTEXT` characters, each
+// taking up to four bytes
+fn read_excerpt(path: &str, span: Span) -> std::io::Result<Excerpt> {
+    let window = CONTEXT * 4;
+    let back = span.offset.min(window);
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start((span.offset - back) as u64))?;
+    let mut bytes = Vec::new();
+    file.take((back + window + 1) as u64)
+        .read_to_end(&mut bytes)?;
+    // The extra byte only tells whether the file goes on
+    let more = bytes.len() > back + window;
+    bytes.truncate(back + window);
+
+    let at = back.min(bytes.len());
+    let mut start = bytes[..at]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    // Skips the rest of a character cut by the window
+    while start < at && bytes[start] & 0xC0 == 0x80 {
+        start += 1;
+    }
+    let end = bytes[at..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |i| at + i);
+
+    let before = String::from_utf8_lossy(&bytes[start..at]);
+    let after = String::from_utf8_lossy(&bytes[at..end]);
+    let first = span.column - before.chars().count();
+    let line = format!("{before}{}", after.trim_end_matches('\r'));
+    let mut excerpt = Excerpt::new(&line, span.column - first + 1);
+    excerpt.column += first - 1;
+    excerpt.truncated |= end == bytes.len() && more;
+
+    Ok(excerpt)
+}
+
+// Prints errors without a location in the same style as reports
+pub fn render_error(message: &str, help: Option<&str>, color: bool) -> String {
+    render_message(message, help, "error", RED, color)
+}
+
+// Same as `render_error` for problems that did not stop the conversion
+pub fn render_warning(message: &str, help: Option<&str>, color: bool) -> String {
+    render_message(message, help, "warning", YELLOW, color)
+}
+
+fn render_message(
+    message: &str,
+    help: Option<&str>,
+    level: &str,
+    style: &str,
+    color: bool,
+) -> String {
+    let mut out = format!(
+        "{}{}",
+        paint(style, level, color),
+        paint(BOLD, &format!(": {message}"), color)
+    );
+    if let Some(help) = help {
+        out += &format!(
+            "\n {} {}",
+            paint(BLUE, "=", color),
+            paint(BOLD, &format!("help: {help}"), color)
+        );
+    }
+
+    out
+}
+
+fn paint(style: &str, text: &str, color: bool) -> String {
+    if color {
+        format!("{style}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ParseOptions, parse_with};
+
+    #[test]
+    fn quotes_short_lines_whole() {
+        let excerpt = Excerpt::new("[1, 2]", 5);
+        assert_eq!(excerpt.text, "[1, 2]");
+        assert_eq!(excerpt.column, 1);
+        assert!(!excerpt.truncated);
+    }
+
+    #[test]
+    fn cuts_long_lines_around_the_column() {
+        let line: String = ('a'..='z').cycle().take(200).collect();
+        let excerpt = Excerpt::new(&line, 100);
+        assert_eq!(excerpt.column, 60);
+        assert_eq!(excerpt.text, line[59..139]);
+        assert!(excerpt.truncated);
+
+        let excerpt = Excerpt::new(&line, 1);
+        assert_eq!((excerpt.column, excerpt.text.len()), (1, CONTEXT));
+    }
+
+    // Reading the excerpt back from a file quotes the same text as taking it
+    // from the source in memory
+    fn assert_same_report(name: &str, source: &str) {
+        let error = match parse_with(source, &ParseOptions::default()) {
+            Err(err) => err,
+            Ok(document) => document.warnings.into_iter().next().expect("a warning"),
+        };
+        let path = std::env::temp_dir().join(format!("tosqweel-{}-{name}", std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let path = path.to_str().unwrap();
+        let from_file = Report::from_file(error.clone(), path).unwrap();
+        let in_memory = Report::new(error, path, source).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(from_file.render(false), in_memory.render(false), "{name}");
+    }
+
+    #[test]
+    fn reads_excerpts_back_from_the_file() {
+        assert_same_report("start", "tru");
+        assert_same_report("line-start", "[1,\nx]");
+        assert_same_report("end", "[1, 2");
+        assert_same_report("crlf", "{\"a\": 1,\r\n \"b\": tru}\r\n");
+        assert_same_report("note", "{\"a\": 1,\r\n \"a\": 2}\r\n");
+        assert_same_report("long", &format!("[{}x]", "1, ".repeat(100)));
+    }
+
+    #[test]
+    fn skips_characters_cut_by_the_window() {
+        // The quoted characters take four bytes each, so the window starts
+        // inside one of them unless they end right before the error
+        for ascii in 0..4 {
+            let source = format!("[\"{}{}\\q\"]", "😀".repeat(60), "a".repeat(ascii));
+            assert_same_report(&format!("utf8-{ascii}"), &source);
+        }
+    }
+}
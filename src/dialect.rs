@@ -0,0 +1,236 @@
+use crate::{
+    JsonObject, Number,
+    error::{Error, Result},
+    sql::ColumnType,
+};
+
+pub trait Dialect {
+    fn quote_identifier(&self, name: &str) -> String;
+
+    fn type_name(&self, ty: ColumnType) -> &'static str;
+
+    fn bool_literal(&self, value: bool) -> &'static str;
+
+    // Column definition for a generated integer primary key
+    fn auto_increment_key(&self, name: &str) -> String;
+
+    // Statement allowing explicit values in a table's generated key
+    fn begin_explicit_keys(&self, _table: &str) -> Option<String> {
+        None
+    }
+
+    // Statement run once a table's explicit keys are inserted
+    fn end_explicit_keys(&self, _table: &str, _key: &str, _last_id: i64) -> Option<String> {
+        None
+    }
+
+    // Whether explicit keys can only be allowed in one table at a time
+    fn exclusive_explicit_keys(&self) -> bool {
+        false
+    }
+
+    // JSON5 infinities and NaN, NULL where the engine cannot store them
+    fn non_finite_literal(&self, _value: f64) -> String {
+        "NULL".to_string()
+    }
+
+    fn string_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    fn literal(&self, value: &JsonObject) -> String {
+        match value {
+            JsonObject::Number(Number::Float(fl)) if !fl.is_finite() => {
+                self.non_finite_literal(*fl)
+            }
+            JsonObject::Number(num) => num.to_string(),
+            JsonObject::Bool(b) => self.bool_literal(*b).to_string(),
+            JsonObject::String(s) => self.string_literal(s),
+            // Nested values have no column to land in
+            JsonObject::Null | JsonObject::Array(_) | JsonObject::Object(_) => "NULL".to_string(),
+        }
+    }
+}
+
+pub fn from_name(name: &str) -> Result<Box<dyn Dialect>> {
+    match name.to_ascii_lowercase().as_str() {
+        "sqlite" => Ok(Box::new(Sqlite)),
+        "postgres" | "postgresql" => Ok(Box::new(Postgres)),
+        "mysql" => Ok(Box::new(MySql)),
+        "sqlserver" | "mssql" => Ok(Box::new(SqlServer)),
+        _ => Err(Error::UnknownDialect(name.to_string())),
+    }
+}
+
+pub struct Sqlite;
+
+impl Dialect for Sqlite {
+    fn quote_identifier(&self, name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    fn type_name(&self, ty: ColumnType) -> &'static str {
+        match ty {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            // SQLite stores booleans as 0 and 1
+            ColumnType::Boolean => "INTEGER",
+            ColumnType::Text => "TEXT",
+            ColumnType::Json => "TEXT",
+        }
+    }
+
+    fn bool_literal(&self, value: bool) -> &'static str {
+        if value { "1" } else { "0" }
+    }
+
+    fn auto_increment_key(&self, name: &str) -> String {
+        format!(
+            "{} INTEGER PRIMARY KEY AUTOINCREMENT",
+            self.quote_identifier(name)
+        )
+    }
+
+    // Literals beyond the double range read back as infinities
+    fn non_finite_literal(&self, value: f64) -> String {
+        match value {
+            f64::INFINITY => "9e999".to_string(),
+            f64::NEG_INFINITY => "-9e999".to_string(),
+            _ => "NULL".to_string(),
+        }
+    }
+}
+
+pub struct Postgres;
+
+impl Dialect for Postgres {
+    fn quote_identifier(&self, name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    fn type_name(&self, ty: ColumnType) -> &'static str {
+        match ty {
+            ColumnType::Integer => "BIGINT",
+            ColumnType::Real => "DOUBLE PRECISION",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Text => "TEXT",
+            ColumnType::Json => "JSONB",
+        }
+    }
+
+    fn bool_literal(&self, value: bool) -> &'static str {
+        if value { "TRUE" } else { "FALSE" }
+    }
+
+    fn auto_increment_key(&self, name: &str) -> String {
+        format!(
+            "{} BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY",
+            self.quote_identifier(name)
+        )
+    }
+
+    fn non_finite_literal(&self, value: f64) -> String {
+        match value {
+            f64::INFINITY => "'Infinity'".to_string(),
+            f64::NEG_INFINITY => "'-Infinity'".to_string(),
+            _ => "'NaN'".to_string(),
+        }
+    }
+
+    // Explicit values do not advance the identity sequence
+    fn end_explicit_keys(&self, table: &str, key: &str, last_id: i64) -> Option<String> {
+        Some(format!(
+            "SELECT setval(pg_get_serial_sequence({}, {}), {});",
+            self.string_literal(&self.quote_identifier(table)),
+            self.string_literal(key),
+            last_id
+        ))
+    }
+}
+
+pub struct MySql;
+
+impl Dialect for MySql {
+    fn quote_identifier(&self, name: &str) -> String {
+        format!("`{}`", name.replace('`', "``"))
+    }
+
+    fn type_name(&self, ty: ColumnType) -> &'static str {
+        match ty {
+            ColumnType::Integer => "BIGINT",
+            ColumnType::Real => "DOUBLE",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Text => "TEXT",
+            ColumnType::Json => "JSON",
+        }
+    }
+
+    fn bool_literal(&self, value: bool) -> &'static str {
+        if value { "TRUE" } else { "FALSE" }
+    }
+
+    fn auto_increment_key(&self, name: &str) -> String {
+        format!(
+            "{} BIGINT AUTO_INCREMENT PRIMARY KEY",
+            self.quote_identifier(name)
+        )
+    }
+
+    // Backslash is an escape character in MySQL string literals
+    fn string_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+    }
+}
+
+pub struct SqlServer;
+
+impl Dialect for SqlServer {
+    fn quote_identifier(&self, name: &str) -> String {
+        format!("[{}]", name.replace(']', "]]"))
+    }
+
+    fn type_name(&self, ty: ColumnType) -> &'static str {
+        match ty {
+            ColumnType::Integer => "BIGINT",
+            ColumnType::Real => "FLOAT",
+            ColumnType::Boolean => "BIT",
+            ColumnType::Text => "NVARCHAR(MAX)",
+            ColumnType::Json => "NVARCHAR(MAX)",
+        }
+    }
+
+    fn bool_literal(&self, value: bool) -> &'static str {
+        if value { "1" } else { "0" }
+    }
+
+    fn auto_increment_key(&self, name: &str) -> String {
+        format!(
+            "{} BIGINT IDENTITY(1,1) PRIMARY KEY",
+            self.quote_identifier(name)
+        )
+    }
+
+    fn begin_explicit_keys(&self, table: &str) -> Option<String> {
+        Some(format!(
+            "SET IDENTITY_INSERT {} ON;",
+            self.quote_identifier(table)
+        ))
+    }
+
+    fn end_explicit_keys(&self, table: &str, _key: &str, _last_id: i64) -> Option<String> {
+        Some(format!(
+            "SET IDENTITY_INSERT {} OFF;",
+            self.quote_identifier(table)
+        ))
+    }
+
+    // IDENTITY_INSERT is on for at most one table per session
+    fn exclusive_explicit_keys(&self) -> bool {
+        true
+    }
+
+    // Unicode text needs the N prefix to survive non-UTF-8 collations
+    fn string_literal(&self, value: &str) -> String {
+        format!("N'{}'", value.replace('\'', "''"))
+    }
+}
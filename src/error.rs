@@ -0,0 +1,338 @@
+use crate::{Span, lexer::Token, sql::ColumnType};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    UnexpectedCharacter {
+        span: Span,
+        found: char,
+    },
+    UnterminatedString {
+        span: Span,
+    },
+    UnterminatedComment {
+        span: Span,
+    },
+    CommentNotAllowed {
+        span: Span,
+    },
+    // A `/` that does not start a comment
+    InvalidComment {
+        span: Span,
+    },
+    ControlCharacter {
+        span: Span,
+        found: char,
+    },
+    InvalidEscape {
+        span: Span,
+        found: char,
+    },
+    InvalidUnicodeEscape {
+        span: Span,
+        digits: String,
+    },
+    UnpairedSurrogate {
+        span: Span,
+        code: u16,
+    },
+    InvalidNumber {
+        span: Span,
+        text: String,
+        reason: &'static str,
+    },
+    // A bare word that is not `true`, `false` or `null`
+    InvalidLiteral {
+        span: Span,
+        found: String,
+    },
+    UnexpectedToken {
+        span: Span,
+        expected: &'static str,
+        found: Token<'static>,
+    },
+    UnexpectedEnd {
+        span: Span,
+        expected: &'static str,
+    },
+    UnterminatedArray {
+        span: Span,
+    },
+    UnterminatedObject {
+        span: Span,
+    },
+    TrailingComma {
+        span: Span,
+    },
+    // Tokens left over after the top-level value
+    TrailingToken {
+        span: Span,
+        found: Token<'static>,
+    },
+    // `span` is the repeated key, `first` its earlier occurrence
+    DuplicateKey {
+        span: Span,
+        first: Span,
+        key: String,
+    },
+    EmptyInput,
+    // A top-level value or array element that cannot become a row
+    NotARecord {
+        span: Span,
+    },
+    FlattenedKeyCollision {
+        span: Span,
+        key: String,
+    },
+    ColumnCollision {
+        table: String,
+        column: String,
+    },
+    // A column whose type had to widen for a later row, reported as a
+    // warning
+    ColumnWidened {
+        table: String,
+        column: String,
+        from: ColumnType,
+        to: ColumnType,
+        row: i64,
+        found: ColumnType,
+    },
+    // A table that never got a column and is left out, reported as a warning
+    EmptyTable {
+        table: String,
+    },
+    // A row that needs a table definition other than the frozen one
+    SchemaChanged {
+        table: String,
+        row: i64,
+        change: String,
+    },
+    Io(String),
+    InvalidUtf8 {
+        offset: usize,
+    },
+    UnknownDialect(String),
+    UnknownNestedMode(String),
+    UnknownDuplicateKeyPolicy(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnexpectedCharacter { found, .. } => {
+                write!(f, "Unexpected character: {}", found)
+            }
+            Error::UnterminatedString { .. } => write!(f, "Unterminated string"),
+            Error::UnterminatedComment { .. } => write!(f, "Unterminated comment"),
+            Error::InvalidComment { .. } => write!(f, "Expected `/` or `*` after `/`"),
+            Error::CommentNotAllowed { .. } => {
+                write!(f, "Comments are not allowed in strict JSON")
+            }
+            Error::ControlCharacter { found, .. } => write!(
+                f,
+                "Unescaped control character in string: U+{:04X}",
+                *found as u32
+            ),
+            Error::InvalidEscape { found, .. } => {
+                write!(f, "Invalid escape sequence: \\{}", found)
+            }
+            Error::InvalidUnicodeEscape { digits, .. } => {
+                write!(f, "Invalid unicode escape: \\u{}", digits)
+            }
+            Error::UnpairedSurrogate { code, .. } => {
+                write!(f, "Unpaired surrogate: \\u{:04x}", code)
+            }
+            Error::InvalidNumber { text, reason, .. } => {
+                write!(f, "Invalid number {}: {}", text, reason)
+            }
+            Error::InvalidLiteral { found, .. } => write!(f, "Invalid literal: {}", found),
+            Error::UnexpectedToken {
+                expected, found, ..
+            } => write!(f, "Expected {}, found {}", expected, found),
+            Error::UnexpectedEnd { expected, .. } => {
+                write!(f, "Expected {}, found end of input", expected)
+            }
+            Error::UnterminatedArray { .. } => write!(f, "Unterminated array"),
+            Error::UnterminatedObject { .. } => write!(f, "Unterminated object"),
+            Error::TrailingComma { .. } => {
+                write!(f, "Trailing commas are not allowed in strict JSON")
+            }
+            Error::TrailingToken { found, .. } => {
+                write!(f, "Unexpected {} after the top-level value", found)
+            }
+            Error::DuplicateKey { key, .. } => write!(f, "Duplicate key \"{}\"", key),
+            Error::EmptyInput => write!(f, "Empty input"),
+            Error::NotARecord { .. } => {
+                write!(f, "Expected an object or an array of objects")
+            }
+            Error::FlattenedKeyCollision { key, .. } => {
+                write!(f, "Flattened key \"{}\" appears more than once", key)
+            }
+            Error::ColumnCollision { table, column } => write!(
+                f,
+                "Column \"{}\" in table \"{}\" collides with a generated column",
+                column, table
+            ),
+            Error::ColumnWidened {
+                table,
+                column,
+                from,
+                to,
+                row,
+                found,
+            } => write!(
+                f,
+                "Column \"{}\" in table \"{}\" widened from {} to {} at row {} ({} found)",
+                column, table, from, to, row, found
+            ),
+            Error::EmptyTable { table } => {
+                write!(f, "Table \"{}\" has no columns and is left out", table)
+            }
+            Error::SchemaChanged { table, row, change } => write!(
+                f,
+                "Row {} of table \"{}\" does not fit the schema already written: {}",
+                row, table, change
+            ),
+            Error::Io(err) => write!(f, "Read failed: {}", err),
+            Error::InvalidUtf8 { offset } => write!(f, "Invalid UTF-8 at byte {}", offset),
+            Error::UnknownDialect(name) => write!(
+                f,
+                "Unknown dialect: {} (expected sqlite, postgres, mysql or sqlserver)",
+                name
+            ),
+            Error::UnknownNestedMode(name) => write!(
+                f,
+                "Unknown nested mode: {} (expected skip, normalize, flatten or json)",
+                name
+            ),
+            Error::UnknownDuplicateKeyPolicy(name) => write!(
+                f,
+                "Unknown duplicate key policy: {} (expected error, warn or first)",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::UnexpectedCharacter { span, .. }
+            | Error::UnterminatedString { span }
+            | Error::UnterminatedComment { span }
+            | Error::CommentNotAllowed { span }
+            | Error::InvalidComment { span }
+            | Error::ControlCharacter { span, .. }
+            | Error::InvalidEscape { span, .. }
+            | Error::InvalidUnicodeEscape { span, .. }
+            | Error::UnpairedSurrogate { span, .. }
+            | Error::InvalidNumber { span, .. }
+            | Error::InvalidLiteral { span, .. }
+            | Error::UnexpectedToken { span, .. }
+            | Error::UnexpectedEnd { span, .. }
+            | Error::UnterminatedArray { span }
+            | Error::UnterminatedObject { span }
+            | Error::TrailingComma { span }
+            | Error::TrailingToken { span, .. }
+            | Error::DuplicateKey { span, .. }
+            | Error::NotARecord { span }
+            | Error::FlattenedKeyCollision { span, .. } => Some(*span),
+            Error::EmptyInput
+            | Error::ColumnCollision { .. }
+            | Error::ColumnWidened { .. }
+            | Error::EmptyTable { .. }
+            | Error::SchemaChanged { .. }
+            | Error::Io(_)
+            | Error::InvalidUtf8 { .. }
+            | Error::UnknownDialect(_)
+            | Error::UnknownNestedMode(_)
+            | Error::UnknownDuplicateKeyPolicy(_) => None,
+        }
+    }
+
+    // A second location worth showing, with a label for it
+    pub fn note(&self) -> Option<(Span, &'static str)> {
+        match self {
+            Error::DuplicateKey { first, .. } => Some((*first, "first defined here")),
+            _ => None,
+        }
+    }
+
+    // Hint on how to fix the input, shown below the diagnostic
+    pub fn help(&self) -> Option<String> {
+        match self {
+            Error::UnexpectedCharacter { found: '\'', .. } => {
+                Some("strings are written with double quotes".to_string())
+            }
+            Error::UnexpectedCharacter { found: '#', .. } => {
+                Some("`#` comments are only accepted with `--hash-comments`".to_string())
+            }
+            Error::InvalidLiteral { found, .. } => keyword_help(found),
+            Error::UnterminatedString { .. } => Some("add a closing `\"`".to_string()),
+            Error::UnterminatedComment { .. } => Some("add a closing `*/`".to_string()),
+            Error::InvalidComment { .. } => Some("comments start with `//` or `/*`".to_string()),
+            Error::UnterminatedArray { .. } => Some("add a closing `]`".to_string()),
+            Error::UnterminatedObject { .. } => Some("add a closing `}`".to_string()),
+            Error::UnexpectedToken {
+                expected: "`:`", ..
+            } => Some("separate the key from its value with `:`".to_string()),
+            Error::UnexpectedToken {
+                expected: "string key",
+                ..
+            } => Some("object keys are written as double-quoted strings".to_string()),
+            Error::UnexpectedToken { expected: "key", .. } => {
+                Some("object keys are written as strings or identifiers".to_string())
+            }
+            Error::TrailingComma { .. } => Some("remove the trailing `,`".to_string()),
+            Error::UnexpectedToken {
+                expected: "`,` or `]`" | "`,` or `}`",
+                found,
+                ..
+            } if found.starts_value() => Some("missing comma between elements".to_string()),
+            Error::UnexpectedToken {
+                found: Token::Comma,
+                ..
+            } => Some("remove the extra `,`".to_string()),
+            Error::TrailingToken { .. } => {
+                Some("a document holds a single value, wrap several in an array".to_string())
+            }
+            Error::DuplicateKey { .. } => Some("remove or rename one of the keys".to_string()),
+            Error::SchemaChanged { .. } => Some(
+                "infer the schema from more records with `--sample`, or from all of them with `--two-pass`"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+// Suggests the literal a misspelt bare word was probably meant to be
+fn keyword_help(word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    ["true", "false", "null"]
+        .into_iter()
+        .map(|keyword| (edit_distance(&word, keyword), keyword))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, keyword)| format!("did you mean `{}`?", keyword))
+}
+
+// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
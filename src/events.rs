@@ -0,0 +1,495 @@
+use std::borrow::Cow;
+
+use crate::{
+    error::{Error, Result},
+    json::{Number, Span, Spanned},
+    lexer::{Lexer, ReadLexer, Token},
+    parser::ParseOptions,
+};
+
+// One step through a document, in the order the input spells it out
+#[derive(Debug, Clone)]
+pub enum Event<'a> {
+    StartObject,
+    Key(Cow<'a, str>),
+    EndObject,
+    StartArray,
+    EndArray,
+    String(Cow<'a, str>),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+// Where the reader is inside an open object or array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    ArrayStart,
+    ArrayAfterComma,
+    ArrayAfterValue,
+    ObjectStart,
+    ObjectAfterComma,
+    ObjectAfterKey,
+    ObjectAfterColon,
+    ObjectAfterValue,
+}
+
+// Checks the grammar while turning tokens into events, keeping only the
+// stack of open containers in memory
+pub struct Events<'a, I> {
+    tokens: I,
+    options: ParseOptions,
+    // Open containers with the span of their opening bracket
+    stack: Vec<(State, Span)>,
+    last_span: Option<Span>,
+    // A top-level value has been completed
+    done: bool,
+    failed: bool,
+    _input: std::marker::PhantomData<&'a str>,
+}
+
+// Events of a document held in memory, strings borrow from it
+pub fn events<'a>(input: &'a str, options: &ParseOptions) -> Events<'a, Lexer<'a>> {
+    Events::new(Lexer::new(input, options), options)
+}
+
+// Events of a document read incrementally, for input larger than memory
+pub fn read_events<R: std::io::Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Events<'static, ReadLexer<R>> {
+    Events::new(ReadLexer::new(reader, options), options)
+}
+
+impl<'a, I> Events<'a, I>
+where
+    I: Iterator<Item = Result<Spanned<Token<'a>>>>,
+{
+    pub fn new(tokens: I, options: &ParseOptions) -> Events<'a, I> {
+        Events {
+            tokens,
+            options: options.clone(),
+            stack: Vec::new(),
+            last_span: None,
+            done: false,
+            failed: false,
+            _input: std::marker::PhantomData,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Spanned<Token<'a>>>> {
+        let token = self.tokens.next().transpose()?;
+        if let Some(token) = &token {
+            self.last_span = Some(token.span);
+        }
+        Ok(token)
+    }
+
+    fn step(&mut self) -> Result<Option<Spanned<Event<'a>>>> {
+        let Some(&(state, open)) = self.stack.last() else {
+            return self.top_level();
+        };
+        let unterminated = || match state {
+            State::ArrayStart | State::ArrayAfterComma | State::ArrayAfterValue => {
+                Error::UnterminatedArray { span: open }
+            }
+            _ => Error::UnterminatedObject { span: open },
+        };
+        // Position of the comma a closing bracket may follow
+        let comma = self.last_span;
+
+        let token = match self.next_token()? {
+            Some(token) => token,
+            None if state == State::ObjectAfterColon => {
+                return Err(Error::UnexpectedEnd {
+                    span: self.last_span.unwrap_or(Span::start()),
+                    expected: "value",
+                });
+            }
+            None => return Err(unterminated()),
+        };
+
+        match (state, token.node) {
+            (
+                State::ArrayStart | State::ArrayAfterComma | State::ArrayAfterValue,
+                Token::RightBracket,
+            )
+            | (
+                State::ObjectStart | State::ObjectAfterComma | State::ObjectAfterValue,
+                Token::RightBrace,
+            ) => {
+                if matches!(state, State::ArrayAfterComma | State::ObjectAfterComma)
+                    && self.options.strict
+                {
+                    return Err(Error::TrailingComma {
+                        span: comma.expect("a comma precedes"),
+                    });
+                }
+                self.stack.pop();
+                let event = match state {
+                    State::ArrayStart | State::ArrayAfterComma | State::ArrayAfterValue => {
+                        Event::EndArray
+                    }
+                    _ => Event::EndObject,
+                };
+                self.close_value();
+                Ok(Some(Spanned {
+                    node: event,
+                    span: token.span,
+                }))
+            }
+            (State::ArrayAfterValue, Token::Comma) => {
+                self.set_state(State::ArrayAfterComma);
+                self.step()
+            }
+            (State::ObjectAfterValue, Token::Comma) => {
+                self.set_state(State::ObjectAfterComma);
+                self.step()
+            }
+            (State::ArrayStart | State::ArrayAfterComma, node) => {
+                self.set_state(State::ArrayAfterValue);
+                self.value(Spanned {
+                    node,
+                    span: token.span,
+                })
+            }
+            (
+                State::ObjectStart | State::ObjectAfterComma,
+                Token::String(key) | Token::Identifier(key),
+            ) => {
+                self.set_state(State::ObjectAfterKey);
+                Ok(Some(Spanned {
+                    node: Event::Key(key),
+                    span: token.span,
+                }))
+            }
+            (State::ObjectStart | State::ObjectAfterComma, found) => Err(Error::UnexpectedToken {
+                span: token.span,
+                expected: if self.options.json5 {
+                    "key"
+                } else {
+                    "string key"
+                },
+                found: found.into_owned(),
+            }),
+            (State::ObjectAfterKey, Token::Colon) => {
+                self.set_state(State::ObjectAfterColon);
+                self.step()
+            }
+            (State::ObjectAfterKey, found) => Err(Error::UnexpectedToken {
+                span: token.span,
+                expected: "`:`",
+                found: found.into_owned(),
+            }),
+            (State::ObjectAfterColon, node) => {
+                self.set_state(State::ObjectAfterValue);
+                self.value(Spanned {
+                    node,
+                    span: token.span,
+                })
+            }
+            (State::ArrayAfterValue, found) => Err(Error::UnexpectedToken {
+                span: token.span,
+                expected: "`,` or `]`",
+                found: found.into_owned(),
+            }),
+            (State::ObjectAfterValue, found) => Err(Error::UnexpectedToken {
+                span: token.span,
+                expected: "`,` or `}`",
+                found: found.into_owned(),
+            }),
+        }
+    }
+
+    // Outside any container: the document's value, then nothing or, in
+    // concatenated mode, further values
+    fn top_level(&mut self) -> Result<Option<Spanned<Event<'a>>>> {
+        let had_token = self.last_span.is_some();
+        let Some(token) = self.next_token()? else {
+            return if had_token {
+                Ok(None)
+            } else {
+                Err(Error::EmptyInput)
+            };
+        };
+        if self.done && !self.options.concatenated {
+            return Err(Error::TrailingToken {
+                span: token.span,
+                found: token.node.into_owned(),
+            });
+        }
+        self.value(token)
+    }
+
+    // Starts a container or emits a scalar
+    fn value(&mut self, token: Spanned<Token<'a>>) -> Result<Option<Spanned<Event<'a>>>> {
+        let event = match token.node {
+            Token::LeftBrace => {
+                self.stack.push((State::ObjectStart, token.span));
+                Event::StartObject
+            }
+            Token::LeftBracket => {
+                self.stack.push((State::ArrayStart, token.span));
+                Event::StartArray
+            }
+            Token::String(s) => Event::String(s),
+            Token::Number(n) => Event::Number(n),
+            Token::Bool(b) => Event::Bool(b),
+            Token::Null => Event::Null,
+            // Bare JSON5 words outside object keys
+            Token::Identifier(word) if word == "true" => Event::Bool(true),
+            Token::Identifier(word) if word == "false" => Event::Bool(false),
+            Token::Identifier(word) if word == "null" => Event::Null,
+            Token::Identifier(word) if word == "Infinity" => {
+                Event::Number(Number::Float(f64::INFINITY))
+            }
+            Token::Identifier(word) if word == "NaN" => Event::Number(Number::Float(f64::NAN)),
+            found => {
+                return Err(Error::UnexpectedToken {
+                    span: token.span,
+                    expected: "value",
+                    found: found.into_owned(),
+                });
+            }
+        };
+        if !matches!(event, Event::StartObject | Event::StartArray) {
+            self.close_value();
+        }
+
+        Ok(Some(Spanned {
+            node: event,
+            span: token.span,
+        }))
+    }
+
+    // Records that a complete value ended outside any container
+    fn close_value(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+
+    fn set_state(&mut self, state: State) {
+        self.stack.last_mut().expect("inside a container").0 = state;
+    }
+}
+
+impl<'a, I> Iterator for Events<'a, I>
+where
+    I: Iterator<Item = Result<Spanned<Token<'a>>>>,
+{
+    type Item = Result<Spanned<Event<'a>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let event = self.step().transpose();
+        if let Some(Err(_)) = event {
+            self.failed = true;
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Chunked, json5};
+
+    fn describe(input: &str, options: &ParseOptions) -> Result<Vec<String>> {
+        events(input, options)
+            .map(|event| event.map(|e| format!("{:?}", e.node)))
+            .collect()
+    }
+
+    #[test]
+    fn walks_a_document_in_order() {
+        let events = describe(
+            r#"{"a": [1, "x", null], "b": {"c": true}, "d": []}"#,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            events,
+            [
+                "StartObject",
+                r#"Key("a")"#,
+                "StartArray",
+                "Number(Integer(1))",
+                r#"String("x")"#,
+                "Null",
+                "EndArray",
+                r#"Key("b")"#,
+                "StartObject",
+                r#"Key("c")"#,
+                "Bool(true)",
+                "EndObject",
+                r#"Key("d")"#,
+                "StartArray",
+                "EndArray",
+                "EndObject",
+            ]
+        );
+    }
+
+    fn error(input: &str) -> Error {
+        describe(input, &ParseOptions::default()).unwrap_err()
+    }
+
+    #[test]
+    fn reports_grammar_errors() {
+        assert!(matches!(error(""), Error::EmptyInput));
+        assert!(matches!(
+            error("[1 2]"),
+            Error::UnexpectedToken {
+                expected: "`,` or `]`",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error(r#"{"a" 1}"#),
+            Error::UnexpectedToken {
+                expected: "`:`",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error("{1: 2}"),
+            Error::UnexpectedToken {
+                expected: "string key",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error(r#"{"a": }"#),
+            Error::UnexpectedToken {
+                expected: "value",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error(r#"{"a": "#),
+            Error::UnexpectedEnd {
+                expected: "value",
+                ..
+            }
+        ));
+        assert!(matches!(error("[1, [2]"), Error::UnterminatedArray { .. }));
+        assert!(matches!(error("[1] 2"), Error::TrailingToken { .. }));
+    }
+
+    #[test]
+    fn points_unterminated_containers_at_their_opening() {
+        let err = error("[1, {\"a\": 2");
+        let Error::UnterminatedObject { span } = err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(span.offset, 4);
+    }
+
+    #[test]
+    fn trailing_commas_are_only_rejected_in_strict_mode() {
+        let input = "[1, {\"a\": 2,},]";
+        assert!(describe(input, &ParseOptions::default()).is_ok());
+
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let err = describe(input, &strict).unwrap_err();
+        let Error::TrailingComma { span } = err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(span.offset, 11);
+    }
+
+    #[test]
+    fn concatenated_values_follow_each_other() {
+        let options = ParseOptions {
+            concatenated: true,
+            ..ParseOptions::default()
+        };
+        let events = describe("{} [] 3", &options).unwrap();
+        assert_eq!(
+            events,
+            [
+                "StartObject",
+                "EndObject",
+                "StartArray",
+                "EndArray",
+                "Number(Integer(3))"
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_after_the_first_error() {
+        let mut events = events("[1 2] [", &ParseOptions::default());
+        assert!(events.by_ref().take_while(Result::is_ok).count() > 0);
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn json5_reserved_words_are_keys_or_values_by_position() {
+        let events = describe(
+            "{null: null, true: false, Infinity: NaN, if: Infinity}",
+            &json5(),
+        )
+        .unwrap();
+        assert_eq!(
+            events,
+            [
+                "StartObject",
+                r#"Key("null")"#,
+                "Null",
+                r#"Key("true")"#,
+                "Bool(false)",
+                r#"Key("Infinity")"#,
+                "Number(Float(NaN))",
+                r#"Key("if")"#,
+                "Number(Float(inf))",
+                "EndObject",
+            ]
+        );
+
+        let err = describe("{a: nope}", &json5()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnexpectedToken {
+                expected: "value",
+                ..
+            }
+        ));
+        let err = describe("{1: 2}", &json5()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnexpectedToken {
+                expected: "key",
+                ..
+            }
+        ));
+        assert_eq!(
+            err.help().as_deref(),
+            Some("object keys are written as strings or identifiers")
+        );
+    }
+
+    #[test]
+    fn reading_in_small_chunks_gives_the_same_events() {
+        let input = format!(
+            r#"// export
+[{{"name": "{}é😀", "n": -12.5e-3, "ok": true}}, /* gap */ null]"#,
+            "long ".repeat(30)
+        );
+        let options = ParseOptions::default();
+        let expected = describe(&input, &options).unwrap();
+        for size in [1, 2, 3, 5, 7, 64] {
+            let reader = Chunked::new(&input, size);
+            let events: Vec<String> = read_events(reader, &options)
+                .map(|event| format!("{:?}", event.unwrap().node))
+                .collect();
+            assert_eq!(events, expected, "chunks of {size} bytes");
+        }
+    }
+}
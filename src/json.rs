@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub enum Number {
+    Float(f64),
+    Integer(i64),
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Float(fl) => write!(f, "{}", fl),
+            Number::Integer(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum JsonObject {
+    Array(Vec<Spanned<JsonObject>>),
+    Bool(bool),
+    Null,
+    Number(Number),
+    Object(Object),
+    String(String),
+}
+
+// Object members in the order their keys appear in the document
+#[derive(Debug, Clone, Default)]
+pub struct Object {
+    entries: Vec<(String, Spanned<JsonObject>)>,
+    index: HashMap<String, usize>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object::default()
+    }
+
+    // A repeated key replaces the value but keeps its original position
+    pub fn insert(
+        &mut self,
+        key: String,
+        value: Spanned<JsonObject>,
+    ) -> Option<Spanned<JsonObject>> {
+        match self.index.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Spanned<JsonObject>> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Spanned<JsonObject>)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a> IntoIterator for &'a Object {
+    type Item = (&'a String, &'a Spanned<JsonObject>);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (String, Spanned<JsonObject>)>,
+        fn(&'a (String, Spanned<JsonObject>)) -> (&'a String, &'a Spanned<JsonObject>),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl FromIterator<(String, Spanned<JsonObject>)> for Object {
+    fn from_iter<I: IntoIterator<Item = (String, Spanned<JsonObject>)>>(iter: I) -> Object {
+        let mut obj = Object::new();
+        for (key, value) in iter {
+            obj.insert(key, value);
+        }
+        obj
+    }
+}
+
+// Location in the source text, `line` and `column` counting from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn start() -> Span {
+        Span {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    // Moves the span past the given characters
+    pub(crate) fn advance(&mut self, text: &str) {
+        self.offset += text.len();
+        for byte in text.bytes() {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Continuation bytes belong to the character already counted
+                self.column += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+// Compact JSON text
+impl std::fmt::Display for JsonObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonObject::Array(arr) => {
+                write!(f, "[")?;
+                for (i, elem) in arr.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", elem.node)?;
+                }
+                write!(f, "]")
+            }
+            JsonObject::Bool(b) => write!(f, "{}", b),
+            JsonObject::Null => write!(f, "null"),
+            // Debug keeps the fraction of whole floats, e.g. `1.0`
+            JsonObject::Number(Number::Float(fl)) if fl.is_finite() => write!(f, "{:?}", fl),
+            // JSON has no spelling for infinities and NaN
+            JsonObject::Number(Number::Float(_)) => write!(f, "null"),
+            JsonObject::Number(num) => write!(f, "{}", num),
+            JsonObject::Object(obj) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in obj.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value.node)?;
+                }
+                write!(f, "}}")
+            }
+            JsonObject::String(s) => write_json_string(f, s),
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
@@ -0,0 +1,1174 @@
+use std::borrow::Cow;
+
+use crate::{
+    error::{Error, Result},
+    json::{Number, Span, Spanned},
+    parser::ParseOptions,
+};
+
+// Strings borrow from the input unless escapes had to be decoded
+#[derive(Debug, Clone)]
+pub enum Token<'a> {
+    LeftBrace,
+    LeftBracket,
+    Number(Number),
+    RightBrace,
+    RightBracket,
+    String(Cow<'a, str>),
+    Colon,
+    Comma,
+    Bool(bool),
+    Null,
+    // Unquoted JSON5 object key
+    Identifier(Cow<'a, str>),
+}
+
+impl Token<'_> {
+    // Whether a value or a key can begin with this token
+    pub fn starts_value(&self) -> bool {
+        !matches!(
+            self,
+            Token::RightBrace | Token::RightBracket | Token::Colon | Token::Comma
+        )
+    }
+
+    // Detaches the token from the input, for errors that outlive it
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::LeftBrace => Token::LeftBrace,
+            Token::LeftBracket => Token::LeftBracket,
+            Token::Number(num) => Token::Number(num),
+            Token::RightBrace => Token::RightBrace,
+            Token::RightBracket => Token::RightBracket,
+            Token::String(s) => Token::String(Cow::Owned(s.into_owned())),
+            Token::Colon => Token::Colon,
+            Token::Comma => Token::Comma,
+            Token::Bool(b) => Token::Bool(b),
+            Token::Null => Token::Null,
+            Token::Identifier(name) => Token::Identifier(Cow::Owned(name.into_owned())),
+        }
+    }
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LeftBrace => write!(f, "LeftBrace"),
+            Token::LeftBracket => write!(f, "LeftBracket"),
+            Token::Number(num) => write!(f, "{}", num),
+            Token::RightBrace => write!(f, "RightBrace"),
+            Token::RightBracket => write!(f, "RightBracket"),
+            Token::String(s) => write!(f, "String({})", s),
+            Token::Colon => write!(f, "Colon"),
+            Token::Comma => write!(f, "Comma"),
+            Token::Bool(b) => write!(f, "Bool({})", b),
+            Token::Null => write!(f, "Null"),
+            Token::Identifier(name) => write!(f, "Identifier({})", name),
+        }
+    }
+}
+
+// Skips the up to and including the delimeters
+fn skip_to_delimeters<'a>(data: &'a str, delims: &str) -> Option<&'a str> {
+    data.find(delims).map(|i| &data[i + delims.len()..])
+}
+
+// Skips a line comment, moving the span to the start of the next line.
+// Both skips moving the span are inlined so it can stay in registers.
+#[inline(always)]
+fn skip_line<'a>(data: &'a str, span: &mut Span) -> &'a str {
+    match data.bytes().position(|b| b == b'\n') {
+        Some(i) => {
+            span.offset += i + 1;
+            span.line += 1;
+            span.column = 1;
+            &data[i + 1..]
+        }
+        None => {
+            span.advance(data);
+            ""
+        }
+    }
+}
+
+// Skips ASCII whitespace, counting lines as it goes. Other whitespace is
+// left to `lex_non_ascii`.
+#[inline(always)]
+fn skip_whitespace<'a>(data: &'a str, span: &mut Span) -> &'a str {
+    let bytes = data.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                span.line += 1;
+                span.column = 1;
+            }
+            b'\t' | b'\x0B' | b'\x0C' | b'\r' | b' ' => span.column += 1,
+            _ => break,
+        }
+        i += 1;
+    }
+    span.offset += i;
+    &data[i..]
+}
+
+fn starts_comment(data: &str, options: &ParseOptions) -> bool {
+    match data.as_bytes()[0] {
+        b'/' => matches!(data.as_bytes().get(1), Some(b'/' | b'*')),
+        b'#' => options.hash_comments,
+        _ => false,
+    }
+}
+
+// Pull lexer handing out one token at a time
+pub struct Lexer<'a> {
+    rest: &'a str,
+    span: Span,
+    options: ParseOptions,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(data: &'a str, options: &ParseOptions) -> Lexer<'a> {
+        Lexer::starting_at(data, Span::start(), options)
+    }
+
+    // Lexes text found at `span` within a larger input
+    pub(crate) fn starting_at(data: &'a str, span: Span, options: &ParseOptions) -> Lexer<'a> {
+        Lexer {
+            rest: data,
+            span,
+            options: options.clone(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Spanned<Token<'a>>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // Whitespace and comments are skipped in locals, stored back once a
+        // token is found
+        let mut rest = self.rest;
+        let mut span = self.span;
+        while !rest.is_empty() {
+            let start = span;
+            match lex_token(rest, &mut span, &self.options) {
+                Ok((token, remaining)) => {
+                    rest = remaining;
+                    if let Some(node) = token {
+                        self.rest = rest;
+                        self.span = span;
+                        return Some(Ok(Spanned { node, span: start }));
+                    }
+                }
+                Err(err) => {
+                    // Lexing stops at the first error
+                    self.rest = "";
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.rest = rest;
+        None
+    }
+}
+
+pub fn tokenize<'a>(data: &'a str, options: &ParseOptions) -> Result<Vec<Spanned<Token<'a>>>> {
+    Lexer::new(data, options).collect()
+}
+
+// Bytes read from the source at a time
+const CHUNK: usize = 64 * 1024;
+
+// Pull lexer over an `io::Read` source that only keeps a window of the
+// input in memory. Tokens are detached from the window as they are handed
+// out.
+pub struct ReadLexer<R> {
+    reader: R,
+    buffer: String,
+    // Read bytes not yet forming a complete character
+    pending: Vec<u8>,
+    pos: usize,
+    // How far past `pos` the end of the next token was already searched
+    scanned: usize,
+    eof: bool,
+    span: Span,
+    options: ParseOptions,
+}
+
+impl<R: std::io::Read> ReadLexer<R> {
+    pub fn new(reader: R, options: &ParseOptions) -> ReadLexer<R> {
+        ReadLexer {
+            reader,
+            buffer: String::new(),
+            pending: Vec::new(),
+            pos: 0,
+            scanned: 0,
+            eof: false,
+            span: Span::start(),
+            options: options.clone(),
+        }
+    }
+
+    // Appends the next chunk of input, dropping what was already lexed
+    fn fill(&mut self) -> Result<()> {
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+
+        let start = self.pending.len();
+        self.pending.resize(start + CHUNK, 0);
+        let read = loop {
+            match self.reader.read(&mut self.pending[start..]) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err.to_string())),
+            }
+        };
+        self.pending.truncate(start + read);
+        self.eof = read == 0;
+
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // A character cut off at the end of the chunk is completed later
+            Err(err) if err.error_len().is_none() && !self.eof => err.valid_up_to(),
+            Err(err) => {
+                return Err(Error::InvalidUtf8 {
+                    offset: self.span.offset + self.buffer.len() + err.valid_up_to(),
+                });
+            }
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).expect("checked above");
+        self.buffer.push_str(text);
+        self.pending.drain(..valid);
+
+        Ok(())
+    }
+}
+
+impl<R: std::io::Read> Iterator for ReadLexer<R> {
+    type Item = Result<Spanned<Token<'static>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos == self.buffer.len() {
+                if self.eof {
+                    return None;
+                }
+                if let Err(err) = self.fill() {
+                    self.eof = true;
+                    self.pos = self.buffer.len();
+                    return Some(Err(err));
+                }
+                continue;
+            }
+
+            let data = &self.buffer[self.pos..];
+            // A token reaching the end of the buffer may continue in the
+            // next chunk. The search for its end resumes where it stopped
+            // rather than starting the token over.
+            if !self.eof
+                && let Some(resume) = scan_token(data.as_bytes(), self.scanned, &self.options)
+            {
+                self.scanned = resume;
+                if let Err(err) = self.fill() {
+                    self.eof = true;
+                    self.pos = self.buffer.len();
+                    return Some(Err(err));
+                }
+                continue;
+            }
+            self.scanned = 0;
+
+            let span = self.span;
+            match lex_token(data, &mut self.span, &self.options) {
+                Ok((token, rest)) => {
+                    let token = token.map(Token::into_owned);
+                    self.pos += data.len() - rest.len();
+                    if let Some(node) = token {
+                        return Some(Ok(Spanned { node, span }));
+                    }
+                }
+                Err(err) => {
+                    self.eof = true;
+                    self.pos = self.buffer.len();
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+// Searches for the end of the token at the start of `data`, continuing
+// from `from` where an earlier search stopped. Returns where to resume once
+// more input is read if the token may continue past the end of `data`.
+// Whitespace needs no search, a run cut short is skipped in two steps.
+fn scan_token(data: &[u8], from: usize, options: &ParseOptions) -> Option<usize> {
+    // Resumes at the end of `data` unless `end` occurs from `start` on
+    let scan_to = |start: usize, end: fn(u8) -> bool| {
+        let start = start.max(from);
+        (!data[start..].iter().any(|&b| end(b))).then_some(data.len())
+    };
+
+    match data[0] {
+        quote @ b'"' | quote @ b'\'' if quote == b'"' || options.json5 => {
+            let mut i = from.max(1);
+            while i < data.len() {
+                match data[i] {
+                    // The escaped byte is skipped, an escape cut short is
+                    // searched again from the backslash
+                    b'\\' if i + 1 == data.len() => return Some(i),
+                    b'\\' => i += 2,
+                    b if b == quote => return None,
+                    _ => i += 1,
+                }
+            }
+            Some(data.len())
+        }
+        b'/' => match data.get(1) {
+            None => Some(0),
+            Some(b'/') => scan_to(2, |b| b == b'\n'),
+            // The `*` of a `*/` cut short is searched again
+            Some(b'*') => {
+                let done = data[from.max(2)..].windows(2).any(|w| w == b"*/");
+                (!done).then_some((data.len() - 1).max(2))
+            }
+            Some(_) => None,
+        },
+        b'#' if options.hash_comments => scan_to(1, |b| b == b'\n'),
+        b'{' | b'}' | b'[' | b']' | b':' | b',' => None,
+        b if b.is_ascii_whitespace() => None,
+        // Numbers, literals and identifiers run up to the next delimiter
+        _ => scan_to(0, |b| {
+            b.is_ascii_whitespace() || b"{}[]:,\"'/#".contains(&b)
+        }),
+    }
+}
+
+// Lexes whitespace, a comment or a single token at the start of `rest`,
+// moving `span` past it
+#[inline(always)]
+fn lex_token<'a>(
+    rest: &'a str,
+    span: &mut Span,
+    options: &ParseOptions,
+) -> Result<(Option<Token<'a>>, &'a str)> {
+    let start = *span;
+    // Only bytes outside ASCII need decoding into a character
+    let b = rest.as_bytes()[0];
+    let next = rest.as_bytes().get(1).copied();
+    let single = |token| (token, &rest[1..]);
+
+    // Arms that return early are tokens that may hold line breaks or
+    // characters outside ASCII
+    let (token, remaining) = match b {
+        b'\t' | b'\n' | b'\x0B' | b'\x0C' | b'\r' | b' ' => {
+            return Ok((None, skip_whitespace(rest, span)));
+        }
+        b'/' | b'#' if options.strict && starts_comment(rest, options) => {
+            return Err(Error::CommentNotAllowed { span: start });
+        }
+        // single line comment
+        b'/' if next == Some(b'/') => return Ok((None, skip_line(rest, span))),
+        b'#' if options.hash_comments => return Ok((None, skip_line(rest, span))),
+        // multi-line comments
+        b'/' if next == Some(b'*') => {
+            let Some(remaining) = skip_to_delimeters(&rest[2..], "*/") else {
+                return Err(Error::UnterminatedComment { span: start });
+            };
+            span.advance(&rest[..rest.len() - remaining.len()]);
+            return Ok((None, remaining));
+        }
+        b'/' => return Err(Error::InvalidComment { span: start }),
+        b'{' => single(Token::LeftBrace),
+        b'}' => single(Token::RightBrace),
+        b'[' => single(Token::LeftBracket),
+        b']' => single(Token::RightBracket),
+        b':' => single(Token::Colon),
+        b',' => single(Token::Comma),
+        b'"' | b'\'' if b == b'"' || options.json5 => {
+            let (s, remaining) = tokenize_string(rest, start, options.json5)?;
+            span.advance(&rest[..rest.len() - remaining.len()]);
+            return Ok((Some(Token::String(s)), remaining));
+        }
+        b if options.json5 && (b.is_ascii_alphabetic() || b == b'$' || b == b'_') => {
+            let (token, remaining) = tokenize_word(rest);
+            span.advance(&rest[..rest.len() - remaining.len()]);
+            return Ok((Some(token), remaining));
+        }
+        b'-' | b'+' | b'.' | b'0'..=b'9' if options.json5 => {
+            let (num, remaining) = tokenize_json5_number(rest, start)?;
+            (Token::Number(num), remaining)
+        }
+        b't' | b'f' => {
+            let (b, remaining) = tokenize_bool(rest, start)?;
+            (Token::Bool(b), remaining)
+        }
+        b'n' => (Token::Null, tokenize_null(rest, start)?),
+        b'-' | b'0'..=b'9' => {
+            let (num, remaining) = tokenize_number(rest, start)?;
+            (Token::Number(num), remaining)
+        }
+        b if b.is_ascii_alphabetic() => return Err(invalid_literal(rest, start)),
+        b if b.is_ascii() => {
+            return Err(Error::UnexpectedCharacter {
+                span: start,
+                found: b as char,
+            });
+        }
+        _ => {
+            let (token, remaining) = lex_non_ascii(rest, start, options)?;
+            span.advance(&rest[..rest.len() - remaining.len()]);
+            return Ok((token, remaining));
+        }
+    };
+
+    // The rest are ASCII without line breaks, their length in bytes is their
+    // length in columns
+    let len = rest.len() - remaining.len();
+    span.offset += len;
+    span.column += len;
+    Ok((Some(token), remaining))
+}
+
+// The rest of `lex_token`, for a token starting outside ASCII
+#[cold]
+fn lex_non_ascii<'a>(
+    rest: &'a str,
+    span: Span,
+    options: &ParseOptions,
+) -> Result<(Option<Token<'a>>, &'a str)> {
+    let c = rest.chars().next().expect("lexing non-empty input");
+    match c {
+        c if c.is_whitespace() => Ok((None, rest.trim_start())),
+        c if options.json5 && is_identifier_start(c) => {
+            let (token, remaining) = tokenize_word(rest);
+            Ok((Some(token), remaining))
+        }
+        c if c.is_alphabetic() => Err(invalid_literal(rest, span)),
+        c => Err(Error::UnexpectedCharacter { span, found: c }),
+    }
+}
+
+// JSON5 strings may also be single-quoted, the closing quote matches the
+// opening one. The input is only copied once an escape needs decoding.
+fn tokenize_string(data: &str, span: Span, json5: bool) -> Result<(Cow<'_, str>, &str)> {
+    let bytes = data.as_bytes();
+    let quote = bytes[0];
+    let mut owned: Option<String> = None;
+    // Start of the input not yet copied into `owned`
+    let mut copied = 1;
+    // Location of the last escape, moved forward rather than recounted
+    let mut at = span;
+    let mut at_offset = 0;
+    let mut i = 1;
+
+    while i < bytes.len() && bytes[i] != quote {
+        match bytes[i] {
+            b'\\' => {
+                let s = owned.get_or_insert_with(String::new);
+                s.push_str(&data[copied..i]);
+                at.advance(&data[at_offset..i]);
+                at_offset = i;
+                let (c, len) = if json5 {
+                    tokenize_json5_escape(&data[i..], at)?
+                } else {
+                    tokenize_escape(&data[i..], at).map(|(c, len)| (Some(c), len))?
+                };
+                s.extend(c);
+                i += len;
+                copied = i;
+            }
+            // Bytes below 0x20 never occur inside multi-byte characters
+            byte if byte < 0x20 => {
+                at.advance(&data[at_offset..i]);
+                return Err(Error::ControlCharacter {
+                    span: at,
+                    found: byte as char,
+                });
+            }
+            _ => i += 1,
+        }
+    }
+
+    if i >= bytes.len() {
+        return Err(Error::UnterminatedString { span });
+    }
+
+    let s = match owned {
+        Some(mut s) => {
+            s.push_str(&data[copied..i]);
+            Cow::Owned(s)
+        }
+        None => Cow::Borrowed(&data[1..i]),
+    };
+    Ok((s, &data[i + 1..]))
+}
+
+// Decodes the escape sequence at the start of `data`, returning the
+// character and the number of input bytes consumed
+fn tokenize_escape(data: &str, span: Span) -> Result<(char, usize)> {
+    let Some(c) = data[1..].chars().next() else {
+        return Err(Error::UnterminatedString { span });
+    };
+
+    let decoded = match c {
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => return tokenize_unicode_escape(data, span),
+        _ => return Err(Error::InvalidEscape { span, found: c }),
+    };
+
+    Ok((decoded, 2))
+}
+
+// JSON5 adds `\'`, `\v`, `\0` and `\xXX`, lets any other character escape
+// to itself and drops an escaped line break, continuing the string on the
+// next line
+fn tokenize_json5_escape(data: &str, span: Span) -> Result<(Option<char>, usize)> {
+    let mut chars = data[1..].chars();
+    let decoded = match chars.next() {
+        Some('\'') => '\'',
+        Some('v') => '\u{b}',
+        Some('0') if !chars.next().is_some_and(|c| c.is_ascii_digit()) => '\0',
+        Some('x') => {
+            let Some(digits) = data
+                .get(2..4)
+                .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            else {
+                if data.len() < 4 && data[2..].bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(Error::UnterminatedString { span });
+                }
+                return Err(Error::InvalidEscape { span, found: 'x' });
+            };
+            let code = u8::from_str_radix(digits, 16).expect("two hex digits fit in u8");
+            return Ok((Some(code as char), 4));
+        }
+        Some('\r') if chars.next() == Some('\n') => return Ok((None, 3)),
+        Some(c @ ('\n' | '\r' | '\u{2028}' | '\u{2029}')) => {
+            return Ok((None, 1 + c.len_utf8()));
+        }
+        Some(c) if c.is_ascii_digit() => return Err(Error::InvalidEscape { span, found: c }),
+        Some('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u') | None => {
+            return tokenize_escape(data, span).map(|(c, len)| (Some(c), len));
+        }
+        Some(c) => return Ok((Some(c), 1 + c.len_utf8())),
+    };
+
+    Ok((Some(decoded), 2))
+}
+
+// `\uXXXX`, where a high surrogate must be followed by an escaped low one
+fn tokenize_unicode_escape(data: &str, span: Span) -> Result<(char, usize)> {
+    let high = hex_code_unit(data, span)?;
+    let unpaired = Error::UnpairedSurrogate { span, code: high };
+    if !(0xD800..0xDC00).contains(&high) {
+        if (0xDC00..0xE000).contains(&high) {
+            return Err(unpaired);
+        }
+        let c = char::from_u32(high as u32).expect("non-surrogate code units are valid chars");
+        return Ok((c, 6));
+    }
+
+    // The input ending before the low half leaves the string unterminated
+    let after = &data[6..];
+    if after.len() < 2 && "\\u".starts_with(after) {
+        return Err(Error::UnterminatedString { span });
+    }
+    if !after.starts_with("\\u") {
+        return Err(unpaired);
+    }
+    let low = hex_code_unit(&data[6..], span)?;
+    if !(0xDC00..0xE000).contains(&low) {
+        return Err(unpaired);
+    }
+
+    let code = 0x10000 + ((high as u32 - 0xD800) << 10) + (low as u32 - 0xDC00);
+    let c = char::from_u32(code).expect("surrogate pairs decode to valid chars");
+    Ok((c, 12))
+}
+
+// Reads the four hex digits following `\u`
+fn hex_code_unit(data: &str, span: Span) -> Result<u16> {
+    match data
+        .get(2..6)
+        .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+    {
+        Some(digits) => Ok(u16::from_str_radix(digits, 16).expect("four hex digits fit in u16")),
+        // Running out of input midway is an unterminated string, which a
+        // reader can still complete from the next chunk
+        None if data.len() < 6 && data[2..].bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Err(Error::UnterminatedString { span })
+        }
+        None => Err(Error::InvalidUnicodeEscape {
+            span,
+            digits: data[2..].chars().take(4).collect(),
+        }),
+    }
+}
+
+// -? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?
+fn tokenize_number(data: &str, span: Span) -> Result<(Number, &str)> {
+    let bytes = data.as_bytes();
+    let mut i = 0;
+    let mut is_float = false;
+
+    if bytes[i] == b'-' {
+        i += 1;
+    }
+
+    match bytes.get(i) {
+        Some(b'0') => {
+            i += 1;
+            if bytes.get(i).is_some_and(|b| b.is_ascii_digit()) {
+                return Err(invalid_number(data, span, "leading zeros are not allowed"));
+            }
+        }
+        Some(b) if b.is_ascii_digit() => i = skip_digits(bytes, i),
+        _ => return Err(invalid_number(data, span, "expected a digit")),
+    }
+
+    if bytes.get(i) == Some(&b'.') {
+        is_float = true;
+        let start = i + 1;
+        i = skip_digits(bytes, start);
+        if i == start {
+            return Err(invalid_number(
+                data,
+                span,
+                "expected a digit after the decimal point",
+            ));
+        }
+    }
+
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        is_float = true;
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let start = i;
+        i = skip_digits(bytes, start);
+        if i == start {
+            return Err(invalid_number(
+                data,
+                span,
+                "expected a digit in the exponent",
+            ));
+        }
+    }
+
+    finish_number(data, i, is_float, span)
+}
+
+// JSON5 numbers may also carry a `+` sign, be hexadecimal, start or end with
+// the decimal point, or be `Infinity` and `NaN`
+fn tokenize_json5_number(data: &str, span: Span) -> Result<(Number, &str)> {
+    let bytes = data.as_bytes();
+    let mut i = 0;
+    let negative = bytes[0] == b'-';
+    if matches!(bytes[0], b'-' | b'+') {
+        i += 1;
+    }
+
+    let sign = if negative { -1.0 } else { 1.0 };
+    for (word, value) in [("Infinity", f64::INFINITY), ("NaN", f64::NAN)] {
+        if data[i..].starts_with(word) {
+            return Ok((Number::Float(sign * value), &data[i + word.len()..]));
+        }
+    }
+
+    if data[i..].starts_with("0x") || data[i..].starts_with("0X") {
+        let start = i + 2;
+        let mut end = start;
+        while end < bytes.len() && bytes[end].is_ascii_hexdigit() {
+            end += 1;
+        }
+        if end == start {
+            return Err(invalid_number(data, span, "expected a hexadecimal digit"));
+        }
+        if data[end..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '.')
+        {
+            return Err(invalid_number(
+                data,
+                span,
+                "unexpected character after number",
+            ));
+        }
+        let digits = &data[start..end];
+        let num = match i64::from_str_radix(digits, 16) {
+            Ok(int) if negative => Number::Integer(-int),
+            Ok(int) => Number::Integer(int),
+            // Beyond i64 the magnitude is kept as a float
+            Err(_) => Number::Float(
+                sign * digits
+                    .chars()
+                    .fold(0.0, |acc, c| acc * 16.0 + c.to_digit(16).unwrap() as f64),
+            ),
+        };
+        return Ok((in_range(num, data, span)?, &data[end..]));
+    }
+
+    let start = i;
+    let mut is_float = false;
+    if bytes.get(i) == Some(&b'0') && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit()) {
+        return Err(invalid_number(data, span, "leading zeros are not allowed"));
+    }
+    i = skip_digits(bytes, i);
+    let mut digits = i - start;
+
+    if bytes.get(i) == Some(&b'.') {
+        is_float = true;
+        let fraction = i + 1;
+        i = skip_digits(bytes, fraction);
+        digits += i - fraction;
+    }
+    if digits == 0 {
+        return Err(invalid_number(data, span, "expected a digit"));
+    }
+
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        is_float = true;
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let exponent = i;
+        i = skip_digits(bytes, exponent);
+        if i == exponent {
+            return Err(invalid_number(
+                data,
+                span,
+                "expected a digit in the exponent",
+            ));
+        }
+    }
+
+    finish_number(data, i, is_float, span)
+}
+
+// Converts the first `len` bytes, which must not run into further
+// number-like characters
+fn finish_number(data: &str, len: usize, is_float: bool, span: Span) -> Result<(Number, &str)> {
+    if data[len..]
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '.' || c == '+' || c == '-')
+    {
+        return Err(invalid_number(
+            data,
+            span,
+            "unexpected character after number",
+        ));
+    }
+
+    let s = &data[..len];
+    let float = || s.parse().expect("the number grammar is valid float syntax");
+    let num = if is_float {
+        Number::Float(float())
+    } else {
+        // Integers beyond i64 keep their magnitude as floats
+        match s.parse() {
+            Ok(int) => Number::Integer(int),
+            Err(_) => Number::Float(float()),
+        }
+    };
+
+    Ok((in_range(num, data, span)?, &data[len..]))
+}
+
+// Only the words `Infinity` and `NaN` may stand for non-finite numbers,
+// digits too large for a double are rejected rather than read as infinite
+fn in_range(num: Number, data: &str, span: Span) -> Result<Number> {
+    match num {
+        Number::Float(fl) if fl.is_infinite() => {
+            Err(invalid_number(data, span, "number out of range"))
+        }
+        num => Ok(num),
+    }
+}
+
+fn skip_digits(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    i
+}
+
+fn invalid_number(data: &str, span: Span, reason: &'static str) -> Error {
+    let text = data
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '.' | '+' | '-'))
+        .collect();
+    Error::InvalidNumber { span, text, reason }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '$' || c == '_'
+}
+
+// A bare JSON5 word. Literals, `Infinity` and `NaN` are also valid object
+// keys, so which one it stands for is left to the parser.
+fn tokenize_word(data: &str) -> (Token<'_>, &str) {
+    let end = data
+        .char_indices()
+        .find(|(_, c)| !is_identifier_start(*c) && !c.is_ascii_digit())
+        .map_or(data.len(), |(i, _)| i);
+
+    (Token::Identifier(Cow::Borrowed(&data[..end])), &data[end..])
+}
+
+fn tokenize_bool(data: &str, span: Span) -> Result<(bool, &str)> {
+    if let Some(rest) = data.strip_prefix("true") {
+        Ok((true, rest))
+    } else if let Some(rest) = data.strip_prefix("false") {
+        Ok((false, rest))
+    } else {
+        Err(invalid_literal(data, span))
+    }
+}
+
+fn tokenize_null(data: &str, span: Span) -> Result<&str> {
+    data.strip_prefix("null")
+        .ok_or_else(|| invalid_literal(data, span))
+}
+
+fn invalid_literal(data: &str, span: Span) -> Error {
+    let found = data
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    Error::InvalidLiteral { span, found }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Chunked, json5};
+
+    fn strings(tokens: Vec<Spanned<Token<'_>>>) -> Vec<String> {
+        tokens.into_iter().map(|t| t.node.to_string()).collect()
+    }
+
+    fn assert_same_chunked(data: &str, options: &ParseOptions) {
+        let expected = strings(tokenize(data, options).unwrap());
+        for size in 1..=13 {
+            let reader = Chunked::new(data, size);
+            let tokens = ReadLexer::new(reader, options).collect::<Result<Vec<_>>>();
+            assert_eq!(strings(tokens.unwrap()), expected, "chunks of {size} bytes");
+        }
+    }
+
+    #[test]
+    fn reader_completes_escapes_split_across_chunks() {
+        // Long enough for every escape to sit at the end of the buffer at
+        // some chunk size
+        let text = format!(
+            r#"["{}\u00e9 \ud83d\ude00 \" \n", "a\u00e9b"]"#,
+            "x".repeat(100)
+        );
+        assert_same_chunked(&text, &ParseOptions::default());
+
+        let text = format!(r#"['{}\x41\u00e9\\\'', 1e3]"#, "x".repeat(100));
+        assert_same_chunked(&text, &json5());
+    }
+
+    #[test]
+    fn reader_splits_multi_byte_characters() {
+        assert_same_chunked(
+            r#"{"naïve": "日本語 😀", "n": -12.5e3}"#,
+            &ParseOptions::default(),
+        );
+    }
+
+    #[test]
+    fn reader_completes_tokens_split_across_chunks() {
+        let digits = "1".repeat(70);
+        let text = format!("[{digits}.5e-3, -{digits}, true, null] // x\n/* a ** b */ [1]");
+        assert_same_chunked(&text, &ParseOptions::default());
+
+        let text = format!("[+{digits}.5, Infinity, NaN, key] # x\n");
+        let options = ParseOptions {
+            hash_comments: true,
+            ..json5()
+        };
+        assert_same_chunked(&text, &options);
+    }
+
+    #[test]
+    fn reader_completes_a_long_number_at_the_chunk_boundary() {
+        // The first read ends right after the `.`
+        let digits = "1".repeat(70);
+        let text = format!("[{}{digits}.5]", " ".repeat(CHUNK - 72));
+        assert_eq!(text.find('.'), Some(CHUNK - 1));
+        let tokens = ReadLexer::new(text.as_bytes(), &ParseOptions::default())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            strings(tokens),
+            strings(tokenize(&text, &ParseOptions::default()).unwrap())
+        );
+    }
+
+    #[test]
+    fn reader_reports_errors_at_the_end() {
+        let reader = Chunked::new(r#"["abc\u00"#, 3);
+        let result = ReadLexer::new(reader, &ParseOptions::default()).collect::<Result<Vec<_>>>();
+        assert!(matches!(result, Err(Error::UnterminatedString { .. })));
+    }
+
+    // The single token `data` lexes to
+    fn lex_one<'a>(data: &'a str, options: &ParseOptions) -> Result<Token<'a>> {
+        let mut tokens = tokenize(data, options)?;
+        assert_eq!(tokens.len(), 1, "{data} lexes to one token");
+        Ok(tokens.remove(0).node)
+    }
+
+    fn string(data: &str, options: &ParseOptions) -> Result<String> {
+        match lex_one(data, options)? {
+            Token::String(s) => Ok(s.into_owned()),
+            token => panic!("{data} lexed to {token}"),
+        }
+    }
+
+    #[test]
+    fn strings_without_escapes_borrow_the_input() {
+        let token = lex_one(r#""plain""#, &ParseOptions::default()).unwrap();
+        assert!(matches!(token, Token::String(Cow::Borrowed("plain"))));
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let options = ParseOptions::default();
+        assert_eq!(string(r#""say \"hi\"""#, &options).unwrap(), r#"say "hi""#);
+        assert_eq!(
+            string(r#""\\ \/ \b \f \n \r \t""#, &options).unwrap(),
+            "\\ / \u{8} \u{c} \n \r \t"
+        );
+        assert_eq!(string(r#""caf\u00e9""#, &options).unwrap(), "café");
+        assert_eq!(string(r#""\u00E9""#, &options).unwrap(), "é");
+    }
+
+    #[test]
+    fn decodes_surrogate_pairs() {
+        let options = ParseOptions::default();
+        assert_eq!(string(r#""\ud83d\ude00""#, &options).unwrap(), "😀");
+        assert_eq!(string(r#""a\uD834\uDD1Eb""#, &options).unwrap(), "a𝄞b");
+    }
+
+    #[test]
+    fn rejects_unpaired_surrogates() {
+        let options = ParseOptions::default();
+        for data in [
+            r#""\ud83d""#,
+            r#""\ud83d x""#,
+            r#""\ud83d\u0041""#,
+            r#""\ude00""#,
+        ] {
+            assert!(
+                matches!(string(data, &options), Err(Error::UnpairedSurrogate { .. })),
+                "{data}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_escapes() {
+        let options = ParseOptions::default();
+        assert!(matches!(
+            string(r#""\q""#, &options),
+            Err(Error::InvalidEscape { found: 'q', .. })
+        ));
+        assert!(matches!(
+            string(r#""\x41""#, &options),
+            Err(Error::InvalidEscape { found: 'x', .. })
+        ));
+        assert!(matches!(
+            string(r#""\u12g4""#, &options),
+            Err(Error::InvalidUnicodeEscape { .. })
+        ));
+        assert!(matches!(
+            string(r#""\u12""#, &options),
+            Err(Error::InvalidUnicodeEscape { .. })
+        ));
+        assert!(matches!(
+            string("\"a\tb\"", &options),
+            Err(Error::ControlCharacter { found: '\t', .. })
+        ));
+    }
+
+    #[test]
+    fn reports_input_ending_inside_an_escape_as_unterminated() {
+        let options = ParseOptions::default();
+        for data in [
+            r#""\"#,
+            r#""\u00"#,
+            r#""\ud83d"#,
+            r#""\ud83d\"#,
+            r#""\ud83d\ude"#,
+        ] {
+            assert!(
+                matches!(
+                    tokenize(data, &options),
+                    Err(Error::UnterminatedString { .. })
+                ),
+                "{data}"
+            );
+        }
+        assert!(matches!(
+            tokenize(r#"'\x4"#, &json5()),
+            Err(Error::UnterminatedString { .. })
+        ));
+    }
+
+    #[test]
+    fn points_at_the_failing_escape() {
+        let err = string(r#""é \q""#, &ParseOptions::default()).unwrap_err();
+        let Error::InvalidEscape { span, .. } = err else {
+            panic!("{err}");
+        };
+        assert_eq!((span.offset, span.line, span.column), (4, 1, 4));
+    }
+
+    #[test]
+    fn decodes_json5_escapes() {
+        let options = json5();
+        assert_eq!(
+            string(r#"'it\'s \x41\v\0'"#, &options).unwrap(),
+            "it's A\u{b}\0"
+        );
+        assert_eq!(string("'one \\\ntwo'", &options).unwrap(), "one two");
+        assert_eq!(string("'one \\\r\ntwo'", &options).unwrap(), "one two");
+        assert_eq!(string(r#"'\q\%'"#, &options).unwrap(), "q%");
+        assert!(matches!(
+            string(r#"'\1'"#, &options),
+            Err(Error::InvalidEscape { found: '1', .. })
+        ));
+        assert!(matches!(
+            string(r#"'\xZ1'"#, &options),
+            Err(Error::InvalidEscape { found: 'x', .. })
+        ));
+    }
+
+    fn number(data: &str, options: &ParseOptions) -> Result<Number> {
+        match lex_one(data, options)? {
+            Token::Number(num) => Ok(num),
+            token => panic!("{data} lexed to {token}"),
+        }
+    }
+
+    fn assert_invalid_number(data: &str, options: &ParseOptions, expected: &str) {
+        match tokenize(data, options) {
+            Err(Error::InvalidNumber { reason, .. }) => assert_eq!(reason, expected, "{data}"),
+            result => panic!("{data} gave {result:?}"),
+        }
+    }
+
+    #[test]
+    fn lexes_numbers() {
+        let options = ParseOptions::default();
+        let cases = [
+            ("0", Number::Integer(0)),
+            ("-0", Number::Integer(0)),
+            ("42", Number::Integer(42)),
+            ("-17", Number::Integer(-17)),
+            ("3.25", Number::Float(3.25)),
+            ("1e10", Number::Float(1e10)),
+            ("2.5E-3", Number::Float(2.5e-3)),
+            ("-0.0e+1", Number::Float(-0.0)),
+            ("1e-400", Number::Float(0.0)),
+            ("1.7976931348623157e308", Number::Float(f64::MAX)),
+            ("9223372036854775807", Number::Integer(i64::MAX)),
+            ("9223372036854775808", Number::Float(9223372036854775808.0)),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(
+                number(data, &options).unwrap().to_string(),
+                expected.to_string(),
+                "{data}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        let options = ParseOptions::default();
+        assert_invalid_number("-", &options, "expected a digit");
+        assert_invalid_number("-a", &options, "expected a digit");
+        assert_invalid_number("01", &options, "leading zeros are not allowed");
+        assert_invalid_number("-007", &options, "leading zeros are not allowed");
+        assert_invalid_number("1.", &options, "expected a digit after the decimal point");
+        assert_invalid_number("1.e5", &options, "expected a digit after the decimal point");
+        assert_invalid_number("1e", &options, "expected a digit in the exponent");
+        assert_invalid_number("1e+", &options, "expected a digit in the exponent");
+        assert_invalid_number("1.2.3", &options, "unexpected character after number");
+        assert_invalid_number("12abc", &options, "unexpected character after number");
+        assert_invalid_number("1-2", &options, "unexpected character after number");
+        assert_invalid_number("1.5e400", &options, "number out of range");
+        assert_invalid_number("-1e309", &options, "number out of range");
+        assert_invalid_number(&"9".repeat(400), &options, "number out of range");
+        assert!(matches!(
+            tokenize(".5", &options),
+            Err(Error::UnexpectedCharacter { found: '.', .. })
+        ));
+        assert!(matches!(
+            tokenize("+1", &options),
+            Err(Error::UnexpectedCharacter { found: '+', .. })
+        ));
+    }
+
+    #[test]
+    fn quotes_the_malformed_number() {
+        let Err(Error::InvalidNumber { text, .. }) =
+            tokenize("[1.2.3, 4]", &ParseOptions::default())
+        else {
+            panic!("1.2.3 is rejected");
+        };
+        assert_eq!(text, "1.2.3");
+    }
+
+    #[test]
+    fn lexes_json5_numbers() {
+        let options = json5();
+        let cases = [
+            ("+1", Number::Integer(1)),
+            (".5", Number::Float(0.5)),
+            ("5.", Number::Float(5.0)),
+            ("-.5e1", Number::Float(-5.0)),
+            ("0x1F", Number::Integer(31)),
+            ("-0xff", Number::Integer(-255)),
+            ("+Infinity", Number::Float(f64::INFINITY)),
+            ("-Infinity", Number::Float(f64::NEG_INFINITY)),
+            ("-NaN", Number::Float(f64::NAN)),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(
+                number(data, &options).unwrap().to_string(),
+                expected.to_string(),
+                "{data}"
+            );
+        }
+
+        assert_invalid_number(".", &options, "expected a digit");
+        assert_invalid_number("0x", &options, "expected a hexadecimal digit");
+        assert_invalid_number("0x1g", &options, "unexpected character after number");
+        assert_invalid_number("01", &options, "leading zeros are not allowed");
+        assert_invalid_number("1e", &options, "expected a digit in the exponent");
+        assert_invalid_number("+1e400", &options, "number out of range");
+        assert_invalid_number(
+            &format!("0x{}", "f".repeat(300)),
+            &options,
+            "number out of range",
+        );
+    }
+}
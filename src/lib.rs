@@ -0,0 +1,27 @@
+// Converts JSONC documents into SQL table definitions and inserts
+//
+// Parse a document with `parse`, or walk it without building a tree with
+// `events`. Infer its tables with `Schema::add_document`, or record by record
+// from `read_records` with `Schema::add_record`, and render them for a
+// `Dialect` with `Schema::create_statements` and `Schema::insert_statements`.
+
+pub mod diagnostic;
+pub mod dialect;
+pub mod error;
+pub mod events;
+pub mod json;
+pub mod lexer;
+pub mod parser;
+pub mod sql;
+#[cfg(test)]
+mod testing;
+
+pub use dialect::Dialect;
+pub use error::{Error, Result};
+pub use events::{Event, events, read_events};
+pub use json::{JsonObject, Number, Object, Span, Spanned};
+pub use parser::{
+    DuplicateKeys, JsonLines, ParseOptions, Records, parse, parse_reader, parse_with, read_lines,
+    read_records, records,
+};
+pub use sql::{ExplicitKeys, Nested, Row, Schema};
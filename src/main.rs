@@ -1,332 +1,566 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use tosqweel::{
+    Error, JsonLines, JsonObject, Records, Spanned, diagnostic,
+    diagnostic::Report,
+    dialect,
+    events::Event,
+    parser::{self, DuplicateKeys, ParseOptions},
+    sql,
+};
+
+// Records read before the tables of a streamed document are written out
+const DEFAULT_SAMPLE: usize = 1000;
+
+// Files picked up from directories
+const EXTENSIONS: [&str; 5] = ["json", "jsonc", "json5", "ndjson", "jsonl"];
+
+// How the tables of a streamed document are inferred
+enum Inference {
+    // From the first records only
+    Sample(usize),
+    // From all records, reading the file a second time for the rows
+    TwoPass,
+}
+
+struct Args {
+    inputs: Vec<Input>,
+    dialect: Box<dyn dialect::Dialect>,
+    nested: sql::Nested,
+    // Rows are written out record by record when set
+    stream: Option<Inference>,
+    skip_bad_lines: bool,
+}
 
-#[derive(Debug, Clone)]
-pub enum Number {
-    Float(f64),
-    Integer(i64),
+// A file to convert, `-` standing for stdin
+struct Input {
+    path: String,
+    parse: ParseOptions,
+    // One document per line
+    ndjson: bool,
 }
 
-impl std::fmt::Display for Number {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Number::Float(fl) => write!(f, "{}", fl),
-            Number::Integer(i) => write!(f, "{}", i),
+impl Input {
+    // The extension can switch on JSON5 or NDJSON for this file alone
+    fn new(path: String, parse: &ParseOptions, ndjson: bool) -> Input {
+        let mut input = Input {
+            parse: parse.clone(),
+            ndjson,
+            path,
+        };
+        match Path::new(&input.path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("json5") => input.parse.json5 = true,
+            Some("ndjson" | "jsonl") => input.ndjson = true,
+            _ => {}
         }
+        input
     }
-}
 
-#[derive(Debug)]
-pub enum JsonObject {
-    Array(Vec<JsonObject>),
-    Bool(bool),
-    Null,
-    Number(Number),
-    Object(HashMap<String, JsonObject>),
-    String(String),
-}
+    fn is_stdin(&self) -> bool {
+        self.path == "-"
+    }
 
-#[derive(Debug, Clone)]
-pub enum Token {
-    LeftBrace,
-    LeftBracket,
-    Number(Number),
-    RightBrace,
-    RightBracket,
-    String(String),
-    Colon,
-    Comma,
-    Bool(bool),
-    Null,
-}
+    // How diagnostics refer to the input
+    fn name(&self) -> &str {
+        if self.is_stdin() {
+            "<stdin>"
+        } else {
+            &self.path
+        }
+    }
 
-impl std::fmt::Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Token::LeftBrace => write!(f, "LeftBrace"),
-            Token::LeftBracket => write!(f, "LeftBracket"),
-            Token::Number(num) => write!(f, "{}", num),
-            Token::RightBrace => write!(f, "RightBrace"),
-            Token::RightBracket => write!(f, "RightBracket"),
-            Token::String(s) => write!(f, "String({})", s),
-            Token::Colon => write!(f, "Colon"),
-            Token::Comma => write!(f, "Comma"),
-            Token::Bool(b) => write!(f, "Bool({})", b),
-            Token::Null => write!(f, "Null"),
+    // The file's records go to a table named after it, `todos.jsonc`
+    // filling `todos`
+    fn table(&self) -> anyhow::Result<&str> {
+        if self.is_stdin() {
+            return Ok("stdin");
         }
+        Path::new(&self.path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", self.path))
     }
-}
 
-// Skips the up to and including the delimeters
-fn skip_to_delimeters(data: &[char], first: char, second: char) -> &[char] {
-    let mut i = 0;
-    loop {
-        while i < data.len() && data[i] != first {
-            i += 1;
+    fn open(&self) -> anyhow::Result<Box<dyn Read>> {
+        if self.is_stdin() {
+            return Ok(Box::new(std::io::stdin().lock()));
         }
-        if i + 1 < data.len() && data[i + 1] != second {
-            i += 1; // skip first
-            continue;
+        let file =
+            fs::File::open(&self.path).map_err(|e| anyhow::anyhow!("{}: {}", self.path, e))?;
+        Ok(Box::new(file))
+    }
+
+    fn read(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.open()?
+            .read_to_end(&mut bytes)
+            .map_err(|e| anyhow::anyhow!("{}: {}", self.name(), e))?;
+        Ok(bytes)
+    }
+
+    fn read_to_string(&self) -> anyhow::Result<String> {
+        let mut content = String::new();
+        self.open()?
+            .read_to_string(&mut content)
+            .map_err(|e| anyhow::anyhow!("{}: {}", self.name(), e))?;
+        Ok(content)
+    }
+
+    // Records read incrementally
+    fn records(&self) -> anyhow::Result<Box<dyn Source>> {
+        let reader = self.open()?;
+        Ok(if self.ndjson {
+            Box::new(parser::read_lines(BufReader::new(reader), &self.parse))
+        } else {
+            Box::new(parser::read_records(reader, &self.parse))
+        })
+    }
+
+    // Diagnostics quote `content` when the input is held in memory, and
+    // otherwise read the lines back from the file if there is one
+    fn report(&self, error: Error, content: Option<&str>) -> Option<Report> {
+        match content {
+            Some(content) => Report::new(error, self.name(), content),
+            None if self.is_stdin() => Report::unquoted(error, self.name()),
+            None => Report::from_file(error, &self.path),
         }
-        i += 2; //skip first and second
-        break;
     }
 
-    &data[i..]
+    // Attaches the source location to errors raised with a span, errors
+    // without one are prefixed by the input's name
+    fn wrap(&self, error: Error, content: Option<&str>) -> anyhow::Error {
+        if error.span().is_none() {
+            return anyhow::Error::new(error).context(self.name().to_string());
+        }
+        let report = self.report(error, content).expect("error has a span");
+        anyhow::Error::new(report)
+    }
 }
 
-// Skips the up to and including the delimeter
-fn skip_to_delimeter(data: &[char], delim: char) -> &[char] {
-    let mut i = 0;
-    while i < data.len() && data[i] != delim {
-        i += 1;
-    }
-    i += 1;
-    &data[i..]
+// Records of the input in order, with the parse warnings met so far
+trait Source: Iterator<Item = tosqweel::Result<Spanned<JsonObject>>> {
+    fn take_warnings(&mut self) -> Vec<Error>;
 }
 
-fn skip_whitespace(data: &[char]) -> &[char] {
-    let mut i = 0;
-    while i < data.len() && data[i].is_whitespace() {
-        i += 1;
+impl<'a, I> Source for Records<I>
+where
+    I: Iterator<Item = tosqweel::Result<Spanned<Event<'a>>>>,
+{
+    fn take_warnings(&mut self) -> Vec<Error> {
+        Records::take_warnings(self)
     }
-    &data[i..]
 }
 
-fn tokenize(data: &[char]) -> anyhow::Result<Vec<Token>> {
-    let mut tokens = Vec::new();
-    let mut rest = skip_whitespace(data);
-
-    while !rest.is_empty() {
-        rest = skip_whitespace(rest);
-        if rest.is_empty() {
-            break;
-        }
+impl<R: BufRead> Source for JsonLines<R> {
+    fn take_warnings(&mut self) -> Vec<Error> {
+        JsonLines::take_warnings(self)
+    }
+}
 
-        match rest[0] {
-            // single line comment
-            '/' if rest[1] == '/' => {
-                rest = skip_to_delimeter(rest, '\n');
-            }
-            // multi-line comments
-            '/' if rest[1] == '*' => {
-                rest = &rest[2..];
-                rest = skip_to_delimeters(rest, '*', '/');
-            }
-            '{' => {
-                tokens.push(Token::LeftBrace);
-                rest = &rest[1..];
-            }
-            '}' => {
-                tokens.push(Token::RightBrace);
-                rest = &rest[1..];
-            }
-            '[' => {
-                tokens.push(Token::LeftBracket);
-                rest = &rest[1..];
+fn parse_args() -> anyhow::Result<Args> {
+    let mut paths = Vec::new();
+    let mut dialect = dialect::from_name("sqlite")?;
+    let mut nested = "normalize".to_string();
+    let mut separator = "_".to_string();
+    let mut parse = ParseOptions::default();
+    let mut stream = None;
+    let mut ndjson = false;
+    let mut skip_bad_lines = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dialect" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --dialect"))?;
+                dialect = dialect::from_name(&name)?;
             }
-            ']' => {
-                tokens.push(Token::RightBracket);
-                rest = &rest[1..];
+            "--nested" => {
+                nested = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --nested"))?;
             }
-            ':' => {
-                tokens.push(Token::Colon);
-                rest = &rest[1..];
+            "--separator" => {
+                separator = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --separator"))?;
             }
-            ',' => {
-                tokens.push(Token::Comma);
-                rest = &rest[1..];
+            "--duplicate-keys" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --duplicate-keys"))?;
+                parse.duplicate_keys = DuplicateKeys::from_name(&name)?;
             }
-            '"' => {
-                let (s, remaining) = tokenize_string(rest)?;
-                tokens.push(Token::String(s));
-                rest = remaining;
+            "--strict-json" => parse.strict = true,
+            "--concatenated" => parse.concatenated = true,
+            "--hash-comments" => parse.hash_comments = true,
+            "--json5" => parse.json5 = true,
+            "--stream" => {
+                stream.get_or_insert(Inference::Sample(DEFAULT_SAMPLE));
             }
-            't' | 'f' => {
-                let (b, remaining) = tokenize_bool(rest)?;
-                tokens.push(Token::Bool(b));
-                rest = remaining;
+            "--sample" => {
+                let size = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --sample"))?;
+                // An empty sample would fix the tables before any column is seen
+                let size = size
+                    .parse()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid value for --sample: {}", size))?;
+                stream = Some(Inference::Sample(size));
             }
-            'n' => {
-                let remaining = tokenize_null(rest)?;
-                tokens.push(Token::Null);
-                rest = remaining;
-            }
-            '-' | '0'..='9' => {
-                let (num, remaining) = tokenize_number(rest)?;
-                tokens.push(Token::Number(num));
-                rest = remaining;
-            }
-            _ => return Err(anyhow::anyhow!("Unexpected character: {}", rest[0])),
+            "--two-pass" => stream = Some(Inference::TwoPass),
+            "--ndjson" => ndjson = true,
+            "--skip-bad-lines" => skip_bad_lines = true,
+            _ if arg.starts_with("--") => return Err(anyhow::anyhow!("Unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(anyhow::anyhow!("Missing args!"));
+    }
+    let mut inputs = Vec::new();
+    for path in &paths {
+        for path in expand(path)? {
+            inputs.push(Input::new(path, &parse, ndjson));
         }
     }
+    // Past a bad document there is no telling where the next record starts
+    if skip_bad_lines && !inputs.iter().any(|input| input.ndjson) {
+        return Err(anyhow::anyhow!(
+            "--skip-bad-lines only applies to NDJSON input"
+        ));
+    }
+    if matches!(stream, Some(Inference::TwoPass)) && inputs.iter().any(Input::is_stdin) {
+        return Err(anyhow::anyhow!("--two-pass cannot read stdin twice"));
+    }
 
-    Ok(tokens)
+    Ok(Args {
+        inputs,
+        dialect,
+        nested: sql::Nested::from_name(&nested, &separator)?,
+        stream,
+        skip_bad_lines,
+    })
 }
 
-fn tokenize_string(data: &[char]) -> anyhow::Result<(String, &[char])> {
-    let mut s = String::new();
-    let mut i = 1;
+// Files named by an argument: `-` or a file as given, the JSON files of a
+// directory, or the files matching `*` and `?` in the last component
+fn expand(arg: &str) -> anyhow::Result<Vec<String>> {
+    let path = Path::new(arg);
+    if path.is_dir() {
+        let files = list_dir(path, |name| {
+            Path::new(name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext))
+        })?;
+        if files.is_empty() {
+            return Err(anyhow::anyhow!("{}: No JSON files found", arg));
+        }
+        return Ok(files);
+    }
 
-    while i < data.len() && data[i] != '"' {
-        s.push(data[i]);
-        i += 1;
+    let pattern = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| name.contains(['*', '?']));
+    match pattern {
+        // Patterns are left to the shell when it expands them
+        Some(pattern) if !path.exists() => {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let files = list_dir(dir, |name| {
+                matches_pattern(&pattern, &name.chars().collect::<Vec<_>>())
+            })?;
+            if files.is_empty() {
+                return Err(anyhow::anyhow!("{}: No files match", arg));
+            }
+            Ok(files)
+        }
+        _ => Ok(vec![arg.to_string()]),
     }
+}
 
-    if i >= data.len() {
-        return Err(anyhow::anyhow!("Unterminated string"));
+// Files of a directory whose names pass `keep`, sorted since directory order
+// differs between systems
+fn list_dir(dir: &Path, keep: impl Fn(&str) -> bool) -> anyhow::Result<Vec<String>> {
+    let entries = fs::read_dir(dir).map_err(|e| anyhow::anyhow!("{}: {}", dir.display(), e))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| anyhow::anyhow!("{}: {}", dir.display(), e))?
+            .path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if path.is_file()
+            && keep(name)
+            && let Some(path) = path.to_str()
+        {
+            files.push(path.to_string());
+        }
     }
+    files.sort();
 
-    Ok((s, &data[i + 1..]))
+    Ok(files)
 }
 
-fn tokenize_number(data: &[char]) -> anyhow::Result<(Number, &[char])> {
-    let mut s = String::new();
-    let mut i = 0;
-    let mut is_float = false;
-
-    if data[i] == '-' {
-        s.push(data[i]);
-        i += 1;
+// `*` matches any run of characters, `?` any single one
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_pattern(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_pattern(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_pattern(rest, &name[1..]),
     }
+}
 
-    while i < data.len() && (data[i].is_ascii_digit() || data[i] == '.') {
-        if data[i] == '.' {
-            is_float = true;
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let color = std::io::stderr().is_terminal();
+            eprintln!("{}", render(&err, false, color));
+            ExitCode::FAILURE
         }
-        s.push(data[i]);
-        i += 1;
     }
+}
 
-    let num = if is_float {
-        Number::Float(s.parse()?)
-    } else {
-        Number::Integer(s.parse()?)
-    };
+// Inputs are converted one after another into a single script
+fn run() -> anyhow::Result<()> {
+    let args = parse_args()?;
+    // Tables already written, with the input they came from
+    let mut tables = HashMap::new();
+    for input in &args.inputs {
+        match &args.stream {
+            Some(inference) => stream(&args, input, inference, &mut tables)?,
+            None if input.ndjson => convert_lines(&args, input, &mut tables)?,
+            None => convert(&args, input, &mut tables)?,
+        }
+    }
 
-    Ok((num, &data[i..]))
+    Ok(())
 }
 
-fn tokenize_bool(data: &[char]) -> anyhow::Result<(bool, &[char])> {
-    if data.len() >= 4 && data[0..4] == ['t', 'r', 'u', 'e'] {
-        Ok((true, &data[4..]))
-    } else if data.len() >= 5 && data[0..5] == ['f', 'a', 'l', 's', 'e'] {
-        Ok((false, &data[5..]))
-    } else {
-        Err(anyhow::anyhow!("Invalid boolean"))
-    }
+fn convert(args: &Args, input: &Input, tables: &mut HashMap<String, String>) -> anyhow::Result<()> {
+    let content = input.read_to_string()?;
+    let document =
+        parser::parse_with(&content, &input.parse).map_err(|e| input.wrap(e, Some(&content)))?;
+    print_warnings(document.warnings, |w| input.report(w, Some(&content)));
+
+    let mut schema = sql::Schema::new(input.table()?, args.nested.clone());
+    let rows = schema
+        .add_document(&document.json)
+        .map_err(|e| input.wrap(e, Some(&content)))?;
+    claim_tables(&schema, input, tables)?;
+    print_tables(&schema, &rows, args.dialect.as_ref());
+
+    Ok(())
 }
 
-fn tokenize_null(data: &[char]) -> anyhow::Result<&[char]> {
-    if data.len() >= 4 && data[0..4] == ['n', 'u', 'l', 'l'] {
-        Ok(&data[4..])
-    } else {
-        Err(anyhow::anyhow!("Invalid null"))
+// NDJSON read whole, each line becoming a record. Invalid UTF-8 only spoils
+// the lines holding it.
+fn convert_lines(
+    args: &Args,
+    input: &Input,
+    tables: &mut HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let bytes = input.read()?;
+    let content = String::from_utf8_lossy(&bytes);
+    let wrap = |e| input.wrap(e, Some(&content));
+
+    let mut lines = parser::read_lines(&bytes[..], &input.parse);
+    let mut schema = sql::Schema::new(input.table()?, args.nested.clone());
+    let mut rows = Vec::new();
+    while let Some(record) = next_record(&mut lines, args.skip_bad_lines, wrap)? {
+        rows.extend(schema.add_record(&record).map_err(wrap)?);
+        print_warnings(lines.take_warnings(), |w| input.report(w, Some(&content)));
     }
-}
+    claim_tables(&schema, input, tables)?;
+    print_tables(&schema, &rows, args.dialect.as_ref());
 
-fn parse_json_array(tokens: &[Token], pos: &mut usize) -> anyhow::Result<JsonObject> {
-    let mut arr = Vec::new();
-    *pos += 1; // skip '['
+    Ok(())
+}
 
-    while *pos < tokens.len() {
-        match &tokens[*pos] {
-            Token::RightBracket => {
-                *pos += 1;
-                return Ok(JsonObject::Array(arr));
+// Writes each record's inserts before reading the next one, so memory is
+// bounded by the largest record rather than the whole document
+fn stream(
+    args: &Args,
+    input: &Input,
+    inference: &Inference,
+    tables: &mut HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let wrap = |e| input.wrap(e, None);
+    let skip_bad_lines = args.skip_bad_lines && input.ndjson;
+    let dialect = args.dialect.as_ref();
+    let mut schema = sql::Schema::new(input.table()?, args.nested.clone());
+    let mut out = BufWriter::new(std::io::stdout().lock());
+
+    let mut records = input.records()?;
+    let mut sample = Vec::new();
+    match inference {
+        Inference::Sample(size) => {
+            for _ in 0..*size {
+                let Some(record) = next_record(records.as_mut(), skip_bad_lines, wrap)? else {
+                    break;
+                };
+                sample.extend(schema.add_record(&record).map_err(wrap)?);
             }
-            Token::Comma => {
-                *pos += 1;
-            }
-            _ => {
-                let val = parse_value(tokens, pos)?;
-                arr.push(val);
+        }
+        Inference::TwoPass => {
+            for record in records.by_ref() {
+                match record {
+                    Ok(record) => {
+                        schema.add_record(&record).map_err(wrap)?;
+                    }
+                    // Reported by the second pass
+                    Err(_) if skip_bad_lines => {}
+                    Err(err) => return Err(wrap(err)),
+                }
             }
+            // The second pass reports the same parse warnings again
+            schema.restart();
+            records = input.records()?;
         }
     }
+    schema.freeze();
+    print_warnings(records.take_warnings(), |w| input.report(w, None));
+    print_schema_warnings(&schema);
 
-    Err(anyhow::anyhow!("Unterminated array"))
-}
+    claim_tables(&schema, input, tables)?;
+    for create in schema.create_statements(dialect) {
+        writeln!(out, "{create}")?;
+    }
+    let mut keys = sql::ExplicitKeys::default();
+    for insert in schema.stream_insert_statements(&sample, dialect, &mut keys) {
+        writeln!(out, "{insert}")?;
+    }
+    while let Some(record) = next_record(records.as_mut(), skip_bad_lines, wrap)? {
+        let rows = schema.add_record(&record).map_err(wrap)?;
+        for insert in schema.stream_insert_statements(&rows, dialect, &mut keys) {
+            writeln!(out, "{insert}")?;
+        }
+        print_warnings(records.take_warnings(), |w| input.report(w, None));
+    }
+    for statement in schema.finish_inserts(keys, dialect) {
+        writeln!(out, "{statement}")?;
+    }
+    out.flush()?;
 
-fn parse_json_object(tokens: &[Token], pos: &mut usize) -> anyhow::Result<JsonObject> {
-    let mut obj = HashMap::new();
-    *pos += 1; // skip '{'
+    Ok(())
+}
 
-    while *pos < tokens.len() {
-        match &tokens[*pos] {
-            Token::RightBrace => {
-                *pos += 1;
-                return Ok(JsonObject::Object(obj));
-            }
-            Token::Comma => {
-                *pos += 1;
+// Pulls the next record. With `skip_bad_lines`, only set for NDJSON input,
+// a bad line is reported and the lines after it read instead.
+fn next_record(
+    source: &mut dyn Source,
+    skip_bad_lines: bool,
+    wrap: impl Fn(Error) -> anyhow::Error,
+) -> anyhow::Result<Option<Spanned<JsonObject>>> {
+    for record in source {
+        match record {
+            Ok(record) => return Ok(Some(record)),
+            Err(err) if skip_bad_lines => {
+                let color = std::io::stderr().is_terminal();
+                eprintln!("{}", render(&wrap(err), true, color));
             }
-            Token::String(key) => {
-                let key = key.clone();
-                *pos += 1;
+            Err(err) => return Err(wrap(err)),
+        }
+    }
 
-                if *pos >= tokens.len() || !matches!(tokens[*pos], Token::Colon) {
-                    return Err(anyhow::anyhow!("Expected colon after key"));
-                }
-                *pos += 1;
+    Ok(None)
+}
 
-                let val = parse_value(tokens, pos)?;
-                obj.insert(key, val);
-            }
-            _ => return Err(anyhow::anyhow!("Expected string key in object")),
+// Each table name can only be defined by one input
+fn claim_tables(
+    schema: &sql::Schema,
+    input: &Input,
+    tables: &mut HashMap<String, String>,
+) -> anyhow::Result<()> {
+    for table in &schema.tables {
+        if let Some(other) = tables.insert(table.name.clone(), input.name().to_string()) {
+            return Err(anyhow::anyhow!(
+                "{}: Table \"{}\" is already defined by {}",
+                input.name(),
+                table.name,
+                other
+            ));
         }
     }
 
-    Err(anyhow::anyhow!("Unterminated object"))
+    Ok(())
 }
 
-fn parse_value(tokens: &[Token], pos: &mut usize) -> anyhow::Result<JsonObject> {
-    if *pos >= tokens.len() {
-        return Err(anyhow::anyhow!("Unexpected end of tokens"));
+// Reports quote their source, other errors keep the help of the structured
+// error they wrap
+fn render(err: &anyhow::Error, warning: bool, color: bool) -> String {
+    if let Some(report) = err.downcast_ref::<Report>() {
+        return if warning {
+            report.render_warning(color)
+        } else {
+            report.render(color)
+        };
     }
 
-    match &tokens[*pos] {
-        Token::LeftBrace => parse_json_object(tokens, pos),
-        Token::LeftBracket => parse_json_array(tokens, pos),
-        Token::String(s) => {
-            *pos += 1;
-            Ok(JsonObject::String(s.clone()))
-        }
-        Token::Number(n) => {
-            *pos += 1;
-            Ok(JsonObject::Number(n.clone()))
-        }
-        Token::Bool(b) => {
-            *pos += 1;
-            Ok(JsonObject::Bool(*b))
-        }
-        Token::Null => {
-            *pos += 1;
-            Ok(JsonObject::Null)
-        }
-        _ => Err(anyhow::anyhow!("Unexpected token: {}", tokens[*pos])),
+    let message = format!("{err:#}");
+    let help = err.downcast_ref::<Error>().and_then(Error::help);
+    if warning {
+        diagnostic::render_warning(&message, help.as_deref(), color)
+    } else {
+        diagnostic::render_error(&message, help.as_deref(), color)
     }
 }
 
-fn parse_object(data: &[char]) -> anyhow::Result<JsonObject> {
-    let tokens = tokenize(data)?;
-
-    if tokens.is_empty() {
-        return Err(anyhow::anyhow!("Empty input"));
+fn print_warnings(warnings: Vec<Error>, report: impl Fn(Error) -> Option<Report>) {
+    let color = std::io::stderr().is_terminal();
+    for warning in warnings {
+        if let Some(report) = report(warning) {
+            eprintln!("{}", report.render_warning(color));
+        }
     }
-
-    let mut pos = 0;
-    parse_value(&tokens, &mut pos)
 }
 
-fn main() -> anyhow::Result<()> {
-    let args: Vec<String> = std::env::args().skip(1).collect();
-    if args.is_empty() {
-        return Err(anyhow::anyhow!("Missing args!"));
+fn print_schema_warnings(schema: &sql::Schema) {
+    let color = std::io::stderr().is_terminal();
+    let print = |warning: &Error| {
+        let help = warning.help();
+        eprintln!(
+            "{}",
+            diagnostic::render_warning(&warning.to_string(), help.as_deref(), color)
+        );
+    };
+    for warning in &schema.warnings {
+        print(warning);
+    }
+    for table in schema.tables.iter().filter(|t| t.is_empty()) {
+        print(&Error::EmptyTable {
+            table: table.name.clone(),
+        });
     }
-    let content = fs::read_to_string(&args[0])?;
-    let chars: Vec<char> = content.chars().collect();
-    let json = parse_object(&chars)?;
-    println!("{json:#?}");
+}
 
-    Ok(())
+fn print_tables(schema: &sql::Schema, rows: &[sql::Row], dialect: &dyn dialect::Dialect) {
+    print_schema_warnings(schema);
+    for create in schema.create_statements(dialect) {
+        println!("{create}");
+    }
+    for insert in schema.insert_statements(rows, dialect) {
+        println!("{insert}");
+    }
 }
@@ -0,0 +1,498 @@
+use std::{collections::HashMap, io::BufRead};
+
+use crate::{
+    error::{Error, Result},
+    events::{Event, Events, events, read_events},
+    json::{JsonObject, Object, Span, Spanned},
+    lexer::{Lexer, ReadLexer},
+};
+
+// What to do when a key appears twice in the same object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    Error,
+    // Later values replace earlier ones, each replacement is reported
+    #[default]
+    Warn,
+    KeepFirst,
+}
+
+impl DuplicateKeys {
+    pub fn from_name(name: &str) -> Result<DuplicateKeys> {
+        match name {
+            "error" => Ok(DuplicateKeys::Error),
+            "warn" => Ok(DuplicateKeys::Warn),
+            "first" => Ok(DuplicateKeys::KeepFirst),
+            _ => Err(Error::UnknownDuplicateKeyPolicy(name.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub duplicate_keys: DuplicateKeys,
+    // Plain RFC 8259 JSON, without comments or trailing commas
+    pub strict: bool,
+    // JSON5 syntax on top of JSONC
+    pub json5: bool,
+    // `#` starts a comment running to the end of the line
+    pub hash_comments: bool,
+    // Several top-level values one after another, read as an array of them
+    pub concatenated: bool,
+}
+
+// A parsed value together with the problems that did not stop parsing
+#[derive(Debug)]
+pub struct Document {
+    pub json: Spanned<JsonObject>,
+    pub warnings: Vec<Error>,
+}
+
+// Assembles the events of a document into a tree of values
+struct Builder {
+    duplicate_keys: DuplicateKeys,
+    warnings: Vec<Error>,
+}
+
+impl Builder {
+    fn value<'a, I>(
+        &mut self,
+        events: &mut I,
+        event: Spanned<Event<'a>>,
+    ) -> Result<Spanned<JsonObject>>
+    where
+        I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+    {
+        let node = match event.node {
+            Event::StartObject => JsonObject::Object(self.object(events)?),
+            Event::StartArray => {
+                let mut arr = Vec::new();
+                loop {
+                    match next_event(events)? {
+                        Spanned {
+                            node: Event::EndArray,
+                            ..
+                        } => break,
+                        elem => arr.push(self.value(events, elem)?),
+                    }
+                }
+                JsonObject::Array(arr)
+            }
+            Event::String(s) => JsonObject::String(s.into_owned()),
+            Event::Number(n) => JsonObject::Number(n),
+            Event::Bool(b) => JsonObject::Bool(b),
+            Event::Null => JsonObject::Null,
+            Event::Key(_) | Event::EndObject | Event::EndArray => {
+                unreachable!("events only close containers they opened")
+            }
+        };
+
+        Ok(Spanned {
+            node,
+            span: event.span,
+        })
+    }
+
+    fn object<'a, I>(&mut self, events: &mut I) -> Result<Object>
+    where
+        I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+    {
+        let mut obj = Object::new();
+        // Where each key was first seen, for duplicate diagnostics
+        let mut keys: HashMap<String, Span> = HashMap::new();
+
+        loop {
+            let event = next_event(events)?;
+            let key = match event.node {
+                Event::EndObject => return Ok(obj),
+                Event::Key(key) => key.into_owned(),
+                _ => unreachable!("objects hold keys followed by values"),
+            };
+            let key_span = event.span;
+
+            let value = next_event(events)?;
+            let val = self.value(events, value)?;
+            match keys.get(&key) {
+                None => {
+                    keys.insert(key.clone(), key_span);
+                    obj.insert(key, val);
+                }
+                Some(&first) => {
+                    let duplicate = Error::DuplicateKey {
+                        span: key_span,
+                        first,
+                        key: key.clone(),
+                    };
+                    match self.duplicate_keys {
+                        DuplicateKeys::Error => return Err(duplicate),
+                        DuplicateKeys::Warn => {
+                            self.warnings.push(duplicate);
+                            obj.insert(key, val);
+                        }
+                        DuplicateKeys::KeepFirst => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn next_event<'a, I>(events: &mut I) -> Result<Spanned<Event<'a>>>
+where
+    I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+{
+    events
+        .next()
+        .expect("events end with an error or a complete document")
+}
+
+// Builds the document's value, or an array of the values in concatenated
+// mode
+fn build<'a, I>(mut events: I, options: &ParseOptions) -> Result<Document>
+where
+    I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+{
+    let mut builder = Builder {
+        duplicate_keys: options.duplicate_keys,
+        warnings: Vec::new(),
+    };
+
+    let first = next_event(&mut events)?;
+    let mut json = builder.value(&mut events, first)?;
+    if options.concatenated {
+        let span = json.span;
+        let mut values = vec![json];
+        while let Some(event) = events.next().transpose()? {
+            values.push(builder.value(&mut events, event)?);
+        }
+        json = Spanned {
+            node: JsonObject::Array(values),
+            span,
+        };
+    } else if let Some(err) = events.next().and_then(|event| event.err()) {
+        return Err(err);
+    }
+
+    Ok(Document {
+        json,
+        warnings: builder.warnings,
+    })
+}
+
+// Parses a JSONC document with the default options, dropping warnings
+pub fn parse(input: &str) -> Result<Spanned<JsonObject>> {
+    parse_with(input, &ParseOptions::default()).map(|doc| doc.json)
+}
+
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Document> {
+    build(events(input, options), options)
+}
+
+// Parses a document without holding its text in memory
+pub fn parse_reader<R: std::io::Read>(reader: R, options: &ParseOptions) -> Result<Document> {
+    build(read_events(reader, options), options)
+}
+
+// Builds the records of a document one at a time: the elements of a
+// top-level array, or the top-level object itself. Only the record being
+// built is held in memory.
+pub struct Records<I> {
+    events: I,
+    builder: Builder,
+    concatenated: bool,
+    in_array: bool,
+    failed: bool,
+}
+
+impl<I> Records<I> {
+    pub fn new(events: I, options: &ParseOptions) -> Records<I> {
+        Records {
+            events,
+            builder: Builder {
+                duplicate_keys: options.duplicate_keys,
+                warnings: Vec::new(),
+            },
+            concatenated: options.concatenated,
+            in_array: false,
+            failed: false,
+        }
+    }
+
+    // Problems found in the records built so far, each reported once
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.builder.warnings)
+    }
+}
+
+impl<'a, I> Records<I>
+where
+    I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+{
+    fn next_record(&mut self) -> Result<Option<Spanned<JsonObject>>> {
+        loop {
+            let Some(event) = self.events.next().transpose()? else {
+                return Ok(None);
+            };
+            match event.node {
+                // Concatenated documents hold one record per value
+                Event::StartArray if !self.in_array && !self.concatenated => {
+                    self.in_array = true;
+                }
+                Event::EndArray if self.in_array => self.in_array = false,
+                Event::StartObject => {
+                    return self.builder.value(&mut self.events, event).map(Some);
+                }
+                _ => return Err(Error::NotARecord { span: event.span }),
+            }
+        }
+    }
+}
+
+impl<'a, I> Iterator for Records<I>
+where
+    I: Iterator<Item = Result<Spanned<Event<'a>>>>,
+{
+    type Item = Result<Spanned<JsonObject>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let record = self.next_record().transpose();
+        if let Some(Err(_)) = record {
+            self.failed = true;
+        }
+        record
+    }
+}
+
+pub fn records<'a>(input: &'a str, options: &ParseOptions) -> Records<Events<'a, Lexer<'a>>> {
+    Records::new(events(input, options), options)
+}
+
+// Records of a document read incrementally
+pub fn read_records<R: std::io::Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Records<Events<'static, ReadLexer<R>>> {
+    Records::new(read_events(reader, options), options)
+}
+
+// Reads newline-delimited JSON, one record per line. Each line is parsed on
+// its own, so after a bad line is reported reading carries on with the next.
+// Lines holding no value are passed over.
+pub struct JsonLines<R> {
+    reader: R,
+    options: ParseOptions,
+    buffer: Vec<u8>,
+    // Start of the next line
+    span: Span,
+    warnings: Vec<Error>,
+    failed: bool,
+}
+
+impl<R: BufRead> JsonLines<R> {
+    pub fn new(reader: R, options: &ParseOptions) -> JsonLines<R> {
+        JsonLines {
+            reader,
+            // Each line holds exactly one value
+            options: ParseOptions {
+                concatenated: false,
+                ..options.clone()
+            },
+            buffer: Vec::new(),
+            span: Span::start(),
+            warnings: Vec::new(),
+            failed: false,
+        }
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn parse_line(&mut self, start: Span) -> Result<Spanned<JsonObject>> {
+        let line = std::str::from_utf8(&self.buffer).map_err(|err| Error::InvalidUtf8 {
+            offset: start.offset + err.valid_up_to(),
+        })?;
+        let line = line.trim_end_matches(['\n', '\r']);
+        let tokens = Lexer::starting_at(line, start, &self.options);
+        let document = build(Events::new(tokens, &self.options), &self.options)?;
+        self.warnings.extend(document.warnings);
+        match document.json.node {
+            JsonObject::Object(_) => Ok(document.json),
+            _ => Err(Error::NotARecord {
+                span: document.json.span,
+            }),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLines<R> {
+    type Item = Result<Spanned<JsonObject>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buffer.clear();
+            match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(len) => {
+                    let start = self.span;
+                    self.span = Span {
+                        offset: start.offset + len,
+                        line: start.line + 1,
+                        column: 1,
+                    };
+                    match self.parse_line(start) {
+                        Err(Error::EmptyInput) => {}
+                        line => return Some(line),
+                    }
+                }
+                Err(err) => {
+                    // The rest of the input cannot be read past a failed read
+                    self.failed = true;
+                    return Some(Err(Error::Io(err.to_string())));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// Records of newline-delimited JSON, one per line
+pub fn read_lines<R: BufRead>(reader: R, options: &ParseOptions) -> JsonLines<R> {
+    JsonLines::new(reader, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_duplicates(duplicate_keys: DuplicateKeys) -> ParseOptions {
+        ParseOptions {
+            duplicate_keys,
+            ..ParseOptions::default()
+        }
+    }
+
+    const DUPLICATED: &str = "{\"a\": 1, \"b\": 2,\n \"a\": 3}";
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let result = parse_with(DUPLICATED, &with_duplicates(DuplicateKeys::Error));
+        let Err(Error::DuplicateKey { span, first, key }) = result else {
+            panic!("duplicate key accepted: {result:?}");
+        };
+        assert_eq!(key, "a");
+        assert_eq!((span.line, span.column), (2, 2));
+        assert_eq!((first.line, first.column), (1, 2));
+    }
+
+    #[test]
+    fn warns_about_duplicate_keys_keeping_the_last_value() {
+        let document = parse_with(DUPLICATED, &with_duplicates(DuplicateKeys::Warn)).unwrap();
+        assert_eq!(document.json.node.to_string(), r#"{"a":3,"b":2}"#);
+        let [Error::DuplicateKey { first, .. }] = document.warnings.as_slice() else {
+            panic!("expected one warning: {:?}", document.warnings);
+        };
+        assert_eq!((first.line, first.column, first.offset), (1, 2, 1));
+    }
+
+    #[test]
+    fn keeps_the_first_of_duplicate_keys_silently() {
+        let options = with_duplicates(DuplicateKeys::KeepFirst);
+        let document = parse_with(DUPLICATED, &options).unwrap();
+        assert_eq!(document.json.node.to_string(), r#"{"a":1,"b":2}"#);
+        assert!(document.warnings.is_empty());
+    }
+
+    fn collect<I: Iterator<Item = Result<Spanned<JsonObject>>>>(records: I) -> Vec<String> {
+        records
+            .map(|record| match record {
+                Ok(record) => record.node.to_string(),
+                Err(err) => format!("error at {}: {err}", err.span().unwrap().line),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_records_of_a_top_level_array() {
+        let options = ParseOptions::default();
+        let input = r#"[{"a": 1}, {"a": {"b": [2]}}]"#;
+        assert_eq!(
+            collect(records(input, &options)),
+            [r#"{"a":1}"#, r#"{"a":{"b":[2]}}"#]
+        );
+        assert_eq!(collect(records(r#"{"a": 1}"#, &options)), [r#"{"a":1}"#]);
+        assert_eq!(
+            collect(records("[{}, 1]", &options)),
+            [
+                "{}",
+                "error at 1: Expected an object or an array of objects"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_concatenated_records() {
+        let options = ParseOptions {
+            concatenated: true,
+            ..ParseOptions::default()
+        };
+        let input = "{\"a\": 1}{\"a\": 2}\n// c\n{\"a\": 3}\n";
+        assert_eq!(
+            collect(read_records(input.as_bytes(), &options)),
+            [r#"{"a":1}"#, r#"{"a":2}"#, r#"{"a":3}"#]
+        );
+        // Arrays are values of their own, not lists of records
+        assert_eq!(
+            collect(records("{}\n[{}]", &options)),
+            [
+                "{}",
+                "error at 2: Expected an object or an array of objects"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_one_record_per_line() {
+        let input = "{\"a\": 1}\n\n   \n// note\n  {\"a\": 2}\r\n{\"a\": }\n{\"a\": 3}";
+        let mut lines = read_lines(input.as_bytes(), &ParseOptions::default());
+        let spans: Vec<_> = lines
+            .by_ref()
+            .map(|line| match line {
+                Ok(record) => (
+                    record.span.line,
+                    record.span.column,
+                    record.node.to_string(),
+                ),
+                Err(err) => {
+                    let span = err.span().unwrap();
+                    (span.line, span.column, err.to_string())
+                }
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (1, 1, r#"{"a":1}"#.to_string()),
+                (5, 3, r#"{"a":2}"#.to_string()),
+                (6, 7, "Expected value, found RightBrace".to_string()),
+                (7, 1, r#"{"a":3}"#.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_keys_per_line() {
+        let input = "{\"a\": 1}\n{\"a\": 1, \"a\": 2}\n";
+        let mut lines = read_lines(input.as_bytes(), &ParseOptions::default());
+        assert_eq!(lines.by_ref().count(), 2);
+        let [Error::DuplicateKey { span, first, .. }] = lines.take_warnings()[..] else {
+            panic!("expected one warning");
+        };
+        assert_eq!((span.line, span.column, first.column), (2, 10, 2));
+        assert_eq!(span.offset, 18);
+    }
+}
@@ -0,0 +1,1111 @@
+use std::collections::HashMap;
+
+use crate::{
+    JsonObject, Number, Object, Spanned,
+    dialect::Dialect,
+    error::{Error, Result},
+};
+
+type Record = Object;
+
+// Surrogate primary key of tables referenced by other tables
+const KEY_COLUMN: &str = "_id";
+// Columns of the child tables holding array elements
+const PARENT_COLUMN: &str = "parent_id";
+const POSITION_COLUMN: &str = "position";
+const VALUE_COLUMN: &str = "value";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Boolean,
+    Text,
+    // Nested values kept as a single JSON document
+    Json,
+}
+
+impl ColumnType {
+    // Nulls and nested values carry no scalar type of their own
+    fn of(value: &JsonObject) -> Option<ColumnType> {
+        match value {
+            JsonObject::Number(Number::Integer(_)) => Some(ColumnType::Integer),
+            JsonObject::Number(Number::Float(_)) => Some(ColumnType::Real),
+            JsonObject::Bool(_) => Some(ColumnType::Boolean),
+            JsonObject::String(_) => Some(ColumnType::Text),
+            JsonObject::Null | JsonObject::Array(_) | JsonObject::Object(_) => None,
+        }
+    }
+
+    // Integers widen to floats, any other mix can only be held as text
+    fn unify(self, other: ColumnType) -> ColumnType {
+        match (self, other) {
+            _ if self == other => self,
+            (ColumnType::Integer, ColumnType::Real) | (ColumnType::Real, ColumnType::Integer) => {
+                ColumnType::Real
+            }
+            _ => ColumnType::Text,
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnType::Integer => write!(f, "integer"),
+            ColumnType::Real => write!(f, "real"),
+            ColumnType::Boolean => write!(f, "boolean"),
+            ColumnType::Text => write!(f, "text"),
+            ColumnType::Json => write!(f, "json"),
+        }
+    }
+}
+
+// How values nested inside a record are mapped onto tables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nested {
+    Skip,
+    Normalize,
+    // Nested keys joined onto their parent's by the separator
+    Flatten(String),
+    Json,
+}
+
+impl Nested {
+    pub fn from_name(name: &str, separator: &str) -> Result<Nested> {
+        match name {
+            "skip" => Ok(Nested::Skip),
+            "normalize" => Ok(Nested::Normalize),
+            "flatten" => Ok(Nested::Flatten(separator.to_string())),
+            "json" => Ok(Nested::Json),
+            _ => Err(Error::UnknownNestedMode(name.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Column {
+    pub name: String,
+    // `None` until a non-null value has been seen
+    pub ty: Option<ColumnType>,
+    // Index of the table this foreign key points at
+    pub references: Option<usize>,
+    // Whether the column links rows rather than holding record data
+    pub generated: bool,
+    // Whether some row holds a null or lacks the key altogether
+    pub nullable: bool,
+}
+
+#[derive(Debug)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    // Whether another table references this one through `KEY_COLUMN`
+    keyed: bool,
+    // Whether its definition has been written out and can no longer change
+    frozen: bool,
+    // Keys whose objects or arrays went to child tables or were flattened
+    nested_keys: Vec<String>,
+    last_id: i64,
+}
+
+impl Table {
+    fn new(name: String) -> Table {
+        Table {
+            name,
+            columns: Vec::new(),
+            keyed: false,
+            frozen: false,
+            nested_keys: Vec::new(),
+            last_id: 0,
+        }
+    }
+
+    fn add_column(
+        &mut self,
+        name: &str,
+        ty: Option<ColumnType>,
+        references: Option<usize>,
+        generated: bool,
+        warnings: &mut Vec<Error>,
+    ) -> Result<()> {
+        if self.keyed && name == KEY_COLUMN {
+            return Err(collision(KEY_COLUMN, &self.name));
+        }
+        match self.columns.iter().position(|c| c.name == name) {
+            Some(index) => {
+                let column = &mut self.columns[index];
+                if column.references != references || column.generated != generated {
+                    return Err(collision(name, &self.name));
+                }
+                match (column.ty, ty) {
+                    (Some(current), Some(ty)) => {
+                        let unified = current.unify(ty);
+                        if unified != current && self.frozen {
+                            return Err(self.changed(format!(
+                                "column \"{}\" would widen from {} to {}",
+                                name, current, unified
+                            )));
+                        }
+                        if unified != current {
+                            warnings.push(Error::ColumnWidened {
+                                table: self.name.clone(),
+                                column: name.to_string(),
+                                from: current,
+                                to: unified,
+                                row: self.last_id,
+                                found: ty,
+                            });
+                            column.ty = Some(unified);
+                        }
+                    }
+                    // Written out as text, which holds any scalar
+                    (None, Some(_)) if self.frozen => column.ty = Some(ColumnType::Text),
+                    (None, Some(_)) => column.ty = ty,
+                    (_, None) => self.set_nullable(index)?,
+                }
+            }
+            None if self.frozen => {
+                return Err(self.changed(format!("column \"{}\" is new", name)));
+            }
+            None => self.columns.push(Column {
+                name: name.to_string(),
+                ty,
+                references,
+                generated,
+                // Earlier rows of the table lack this column
+                nullable: ty.is_none() || self.last_id > 1,
+            }),
+        }
+
+        Ok(())
+    }
+
+    fn set_nullable(&mut self, index: usize) -> Result<()> {
+        let column = &mut self.columns[index];
+        if self.frozen && !column.nullable {
+            return Err(self.changed(format!(
+                "column \"{}\" would hold a null",
+                self.columns[index].name
+            )));
+        }
+        column.nullable = true;
+
+        Ok(())
+    }
+
+    // A null seen under the key before its nested value left a column that
+    // never held anything, the child table or flattened columns stand in
+    // for it
+    fn mark_nested(&mut self, key: &str) {
+        if self.nested_keys.iter().any(|k| k == key) {
+            return;
+        }
+        self.nested_keys.push(key.to_string());
+        if !self.frozen {
+            self.columns.retain(|c| {
+                c.name != key || c.ty.is_some() || c.generated || c.references.is_some()
+            });
+        }
+    }
+
+    pub fn keyed(&self) -> bool {
+        self.keyed
+    }
+
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    // No dialect accepts a table without columns, so such tables are left
+    // out of the statements
+    pub fn is_empty(&self) -> bool {
+        !self.keyed && self.columns.is_empty()
+    }
+
+    fn changed(&self, change: String) -> Error {
+        Error::SchemaChanged {
+            table: self.name.clone(),
+            row: self.last_id,
+            change,
+        }
+    }
+
+    fn create_statement(&self, tables: &[Table], dialect: &dyn Dialect) -> String {
+        let mut lines = Vec::new();
+        if self.keyed {
+            lines.push(format!("    {}", dialect.auto_increment_key(KEY_COLUMN)));
+        }
+        for column in &self.columns {
+            let ty = column.ty.unwrap_or(ColumnType::Text);
+            lines.push(format!(
+                "    {} {}{}",
+                dialect.quote_identifier(&column.name),
+                dialect.type_name(ty),
+                if column.nullable { "" } else { " NOT NULL" }
+            ));
+        }
+        for column in &self.columns {
+            if let Some(referenced) = column.references {
+                lines.push(format!(
+                    "    FOREIGN KEY ({}) REFERENCES {} ({})",
+                    dialect.quote_identifier(&column.name),
+                    dialect.quote_identifier(&tables[referenced].name),
+                    dialect.quote_identifier(KEY_COLUMN)
+                ));
+            }
+        }
+
+        format!(
+            "CREATE TABLE {} (\n{}\n);",
+            dialect.quote_identifier(&self.name),
+            lines.join(",\n")
+        )
+    }
+
+    fn insert_statement(&self, row: &Row, dialect: &dyn Dialect) -> String {
+        let mut columns = Vec::new();
+        let mut values = Vec::new();
+        if self.keyed {
+            columns.push(dialect.quote_identifier(KEY_COLUMN));
+            values.push(row.id.to_string());
+        }
+        for column in &self.columns {
+            columns.push(dialect.quote_identifier(&column.name));
+            values.push(match row.values.get(&column.name) {
+                None | Some(JsonObject::Null) => "NULL".to_string(),
+                // Values of a column widened to text keep their JSON spelling
+                Some(value @ (JsonObject::Number(_) | JsonObject::Bool(_)))
+                    if matches!(column.ty, Some(ColumnType::Text | ColumnType::Json)) =>
+                {
+                    dialect.string_literal(&text_spelling(value, column.ty))
+                }
+                Some(value) => dialect.literal(value),
+            });
+        }
+
+        format!(
+            "INSERT INTO {} ({}) VALUES ({});",
+            dialect.quote_identifier(&self.name),
+            columns.join(", "),
+            values.join(", ")
+        )
+    }
+}
+
+// JSON has no spelling for infinities and NaN, text columns take JSON5's
+// rather than a JSON null
+fn text_spelling(value: &JsonObject, ty: Option<ColumnType>) -> String {
+    match value {
+        JsonObject::Number(Number::Float(fl))
+            if !fl.is_finite() && ty == Some(ColumnType::Text) =>
+        {
+            match *fl {
+                f64::INFINITY => "Infinity",
+                f64::NEG_INFINITY => "-Infinity",
+                _ => "NaN",
+            }
+            .to_string()
+        }
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub struct Row {
+    pub table: usize,
+    pub id: i64,
+    pub values: HashMap<String, JsonObject>,
+}
+
+// Tables whose explicit keys are allowed while a document's rows are
+// inserted a few at a time
+#[derive(Debug, Default)]
+pub struct ExplicitKeys {
+    open: Vec<usize>,
+}
+
+// Tables inferred from a document, the first one holding the top-level records
+#[derive(Debug)]
+pub struct Schema {
+    pub tables: Vec<Table>,
+    // Type widening decisions made while inferring columns
+    pub warnings: Vec<Error>,
+    nested: Nested,
+}
+
+impl Schema {
+    pub fn new(name: &str, nested: Nested) -> Schema {
+        Schema {
+            tables: vec![Table::new(name.to_string())],
+            warnings: Vec::new(),
+            nested,
+        }
+    }
+
+    // Infers columns from the document and returns the rows it yields
+    pub fn add_document(&mut self, json: &Spanned<JsonObject>) -> Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        for record in records(json)? {
+            self.add_top_level(record, &mut rows)?;
+        }
+
+        Ok(rows)
+    }
+
+    // Same as `add_document` for a single record, when a document is read
+    // one record at a time
+    pub fn add_record(&mut self, record: &Spanned<JsonObject>) -> Result<Vec<Row>> {
+        let JsonObject::Object(obj) = &record.node else {
+            return Err(Error::NotARecord { span: record.span });
+        };
+        let mut rows = Vec::new();
+        self.add_top_level(obj, &mut rows)?;
+
+        Ok(rows)
+    }
+
+    // Fixes the tables as they are, once their definitions are written out.
+    // Later records needing other columns or types are rejected.
+    pub fn freeze(&mut self) {
+        for table in &mut self.tables {
+            table.frozen = true;
+        }
+    }
+
+    // Numbers rows from the start again, for a second pass over the records
+    pub fn restart(&mut self) {
+        for table in &mut self.tables {
+            table.last_id = 0;
+        }
+    }
+
+    fn add_top_level(&mut self, record: &Record, rows: &mut Vec<Row>) -> Result<()> {
+        match &self.nested {
+            Nested::Flatten(separator) => {
+                let (flat, objects) = flatten(record, separator)?;
+                for name in &objects {
+                    self.tables[0].mark_nested(name);
+                }
+                self.add_row(0, &flat, HashMap::new(), rows)?;
+            }
+            _ => {
+                self.add_row(0, record, HashMap::new(), rows)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Rows of nested objects are pushed before the row referencing them,
+    // rows of array elements after it. `values` holds the link columns the
+    // caller has already added to the table.
+    fn add_row(
+        &mut self,
+        table: usize,
+        record: &Record,
+        mut values: HashMap<String, JsonObject>,
+        rows: &mut Vec<Row>,
+    ) -> Result<i64> {
+        let table_ref = &mut self.tables[table];
+        table_ref.last_id += 1;
+        let id = table_ref.last_id;
+
+        let mut arrays = Vec::new();
+        for (key, value) in record {
+            match &value.node {
+                JsonObject::Object(obj) if self.nested == Nested::Normalize => {
+                    self.tables[table].mark_nested(key);
+                    let child = self.child_table(table, key)?;
+                    self.mark_keyed(child, self.tables[child].last_id + 1)?;
+                    let child_id = self.add_row(child, obj, HashMap::new(), rows)?;
+                    let column = format!("{key}_id");
+                    self.tables[table].add_column(
+                        &column,
+                        Some(ColumnType::Integer),
+                        Some(child),
+                        true,
+                        &mut self.warnings,
+                    )?;
+                    values.insert(column, integer(child_id));
+                }
+                JsonObject::Array(arr) if self.nested == Nested::Normalize => {
+                    self.tables[table].mark_nested(key);
+                    arrays.push((key, arr));
+                }
+                // Only leaves the link to the child table or the flattened
+                // columns empty
+                JsonObject::Null
+                    if matches!(self.nested, Nested::Normalize | Nested::Flatten(_))
+                        && self.tables[table].nested_keys.contains(key) => {}
+                JsonObject::Array(_) | JsonObject::Object(_) if self.nested == Nested::Json => {
+                    self.tables[table].add_column(
+                        key,
+                        Some(ColumnType::Json),
+                        None,
+                        false,
+                        &mut self.warnings,
+                    )?;
+                    values.insert(key.clone(), JsonObject::String(value.node.to_string()));
+                }
+                JsonObject::Array(_) | JsonObject::Object(_) => {}
+                _ => {
+                    self.tables[table].add_column(
+                        key,
+                        ColumnType::of(&value.node),
+                        None,
+                        false,
+                        &mut self.warnings,
+                    )?;
+                    // Some engines can only store infinities and NaN as NULL
+                    if let JsonObject::Number(Number::Float(fl)) = value.node
+                        && !fl.is_finite()
+                    {
+                        let table_ref = &mut self.tables[table];
+                        if let Some(index) = table_ref.columns.iter().position(|c| c.name == *key) {
+                            table_ref.set_nullable(index)?;
+                        }
+                    }
+                    values.insert(key.clone(), value.node.clone());
+                }
+            }
+        }
+        let table_ref = &mut self.tables[table];
+        for index in 0..table_ref.columns.len() {
+            if !values.contains_key(&table_ref.columns[index].name) {
+                table_ref.set_nullable(index)?;
+            }
+        }
+        rows.push(Row { table, id, values });
+
+        for (key, arr) in arrays {
+            self.add_array(table, id, key, arr, rows)?;
+        }
+
+        Ok(id)
+    }
+
+    // Each element becomes a row pointing back at its parent row. Objects
+    // spread over columns, any other element lands in `VALUE_COLUMN`.
+    fn add_array(
+        &mut self,
+        parent: usize,
+        parent_id: i64,
+        key: &str,
+        arr: &[Spanned<JsonObject>],
+        rows: &mut Vec<Row>,
+    ) -> Result<()> {
+        // An empty array needs neither the child table nor the parent key
+        if arr.is_empty() {
+            return Ok(());
+        }
+        self.mark_keyed(parent, parent_id)?;
+        let child = self.child_table(parent, key)?;
+
+        for (position, elem) in arr.iter().enumerate() {
+            let table = &mut self.tables[child];
+            table.add_column(
+                PARENT_COLUMN,
+                Some(ColumnType::Integer),
+                Some(parent),
+                true,
+                &mut self.warnings,
+            )?;
+            table.add_column(
+                POSITION_COLUMN,
+                Some(ColumnType::Integer),
+                None,
+                true,
+                &mut self.warnings,
+            )?;
+            let values = HashMap::from([
+                (PARENT_COLUMN.to_string(), integer(parent_id)),
+                (POSITION_COLUMN.to_string(), integer(position as i64)),
+            ]);
+
+            match &elem.node {
+                JsonObject::Object(obj) => self.add_row(child, obj, values, rows)?,
+                _ => {
+                    let record = Object::from_iter([(VALUE_COLUMN.to_string(), elem.clone())]);
+                    self.add_row(child, &record, values, rows)?
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    // Finds or creates the table holding values nested under `key`
+    fn child_table(&mut self, parent: usize, key: &str) -> Result<usize> {
+        let name = format!("{}_{}", self.tables[parent].name, key);
+        match self.tables.iter().position(|t| t.name == name) {
+            Some(child) => Ok(child),
+            None if self.tables[parent].frozen => Err(Error::SchemaChanged {
+                table: name,
+                row: 1,
+                change: "the table is new".to_string(),
+            }),
+            None => {
+                self.tables.push(Table::new(name));
+                Ok(self.tables.len() - 1)
+            }
+        }
+    }
+
+    // `row` is the row of `table` about to reference or be referenced
+    fn mark_keyed(&mut self, table: usize, row: i64) -> Result<()> {
+        let table = &mut self.tables[table];
+        if table.columns.iter().any(|c| c.name == KEY_COLUMN) {
+            return Err(collision(KEY_COLUMN, &table.name));
+        }
+        if table.frozen && !table.keyed {
+            return Err(Error::SchemaChanged {
+                table: table.name.clone(),
+                row,
+                change: format!("the table would need a \"{}\" column", KEY_COLUMN),
+            });
+        }
+        table.keyed = true;
+
+        Ok(())
+    }
+
+    // Referenced tables come before the tables pointing at them
+    fn dependency_order(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        for table in 0..self.tables.len() {
+            self.visit(table, &mut order);
+        }
+        order
+    }
+
+    fn visit(&self, table: usize, order: &mut Vec<usize>) {
+        if order.contains(&table) {
+            return;
+        }
+        for column in &self.tables[table].columns {
+            if let Some(referenced) = column.references {
+                self.visit(referenced, order);
+            }
+        }
+        order.push(table);
+    }
+
+    pub fn create_statements(&self, dialect: &dyn Dialect) -> Vec<String> {
+        self.dependency_order()
+            .into_iter()
+            .filter(|&table| !self.tables[table].is_empty())
+            .map(|table| self.tables[table].create_statement(&self.tables, dialect))
+            .collect()
+    }
+
+    // Rows are grouped per table so every foreign key is inserted before use
+    pub fn insert_statements(&self, rows: &[Row], dialect: &dyn Dialect) -> Vec<String> {
+        let mut statements = Vec::new();
+        for index in self.dependency_order() {
+            let table = &self.tables[index];
+            let table_rows: Vec<&Row> = rows.iter().filter(|r| r.table == index).collect();
+            if table_rows.is_empty() || table.is_empty() {
+                continue;
+            }
+
+            if table.keyed {
+                statements.extend(dialect.begin_explicit_keys(&table.name));
+            }
+            for row in &table_rows {
+                statements.push(table.insert_statement(row, dialect));
+            }
+            if table.keyed {
+                statements.extend(dialect.end_explicit_keys(
+                    &table.name,
+                    KEY_COLUMN,
+                    table.last_id,
+                ));
+            }
+        }
+
+        statements
+    }
+
+    // Same as `insert_statements` for rows inserted record by record.
+    // Explicit keys are allowed in a table the first time it is filled and
+    // left so until `finish_inserts`, unless the dialect only allows them in
+    // one table at a time.
+    pub fn stream_insert_statements(
+        &self,
+        rows: &[Row],
+        dialect: &dyn Dialect,
+        keys: &mut ExplicitKeys,
+    ) -> Vec<String> {
+        let mut statements = Vec::new();
+        for index in self.dependency_order() {
+            let table = &self.tables[index];
+            let mut table_rows = rows.iter().filter(|r| r.table == index).peekable();
+            if table_rows.peek().is_none() || table.is_empty() {
+                continue;
+            }
+
+            if table.keyed && !keys.open.contains(&index) {
+                if dialect.exclusive_explicit_keys() {
+                    statements.extend(self.end_explicit_keys(keys, dialect));
+                }
+                statements.extend(dialect.begin_explicit_keys(&table.name));
+                keys.open.push(index);
+            }
+            for row in table_rows {
+                statements.push(table.insert_statement(row, dialect));
+            }
+        }
+
+        statements
+    }
+
+    // Closes what `stream_insert_statements` left open, once every row is
+    // inserted
+    pub fn finish_inserts(&self, mut keys: ExplicitKeys, dialect: &dyn Dialect) -> Vec<String> {
+        self.end_explicit_keys(&mut keys, dialect)
+    }
+
+    fn end_explicit_keys(&self, keys: &mut ExplicitKeys, dialect: &dyn Dialect) -> Vec<String> {
+        keys.open
+            .drain(..)
+            .filter_map(|index| {
+                let table = &self.tables[index];
+                dialect.end_explicit_keys(&table.name, KEY_COLUMN, table.last_id)
+            })
+            .collect()
+    }
+}
+
+// A document is either a single record or an array of records
+fn records(json: &Spanned<JsonObject>) -> Result<Vec<&Record>> {
+    match &json.node {
+        JsonObject::Object(obj) => Ok(vec![obj]),
+        JsonObject::Array(arr) => arr
+            .iter()
+            .map(|elem| match &elem.node {
+                JsonObject::Object(obj) => Ok(obj),
+                _ => Err(Error::NotARecord { span: elem.span }),
+            })
+            .collect(),
+        _ => Err(Error::NotARecord { span: json.span }),
+    }
+}
+
+// Also returns the names of the objects spread over columns
+fn flatten(record: &Record, separator: &str) -> Result<(Record, Vec<String>)> {
+    let mut flat = Object::new();
+    let mut objects = Vec::new();
+    flatten_into(&mut flat, &mut objects, "", record, separator)?;
+    Ok((flat, objects))
+}
+
+// Arrays cannot be spread over a fixed set of columns and are kept as JSON text
+fn flatten_into(
+    flat: &mut Record,
+    objects: &mut Vec<String>,
+    prefix: &str,
+    record: &Record,
+    separator: &str,
+) -> Result<()> {
+    for (key, value) in record {
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}{separator}{key}")
+        };
+        let node = match &value.node {
+            JsonObject::Object(obj) => {
+                flatten_into(flat, objects, &name, obj, separator)?;
+                objects.push(name);
+                continue;
+            }
+            JsonObject::Array(_) => JsonObject::String(value.node.to_string()),
+            node => node.clone(),
+        };
+        let flattened = Spanned {
+            node,
+            span: value.span,
+        };
+        if flat.insert(name.clone(), flattened).is_some() {
+            return Err(Error::FlattenedKeyCollision {
+                span: value.span,
+                key: name,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn integer(value: i64) -> JsonObject {
+    JsonObject::Number(Number::Integer(value))
+}
+
+fn collision(column: &str, table: &str) -> Error {
+    Error::ColumnCollision {
+        table: table.to_string(),
+        column: column.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialect::{MySql, Postgres, SqlServer, Sqlite},
+        parse_with,
+        testing::json5,
+    };
+
+    fn infer(input: &str, nested: Nested) -> (Schema, Vec<Row>) {
+        let document = parse_with(input, &json5()).unwrap();
+        let mut schema = Schema::new("t", nested);
+        let rows = schema.add_document(&document.json).unwrap();
+        (schema, rows)
+    }
+
+    fn column<'a>(schema: &'a Schema, table: &str, name: &str) -> &'a Column {
+        let table = schema.tables.iter().find(|t| t.name == table).unwrap();
+        table.columns.iter().find(|c| c.name == name).unwrap()
+    }
+
+    // Create statements followed by inserts
+    fn statements(input: &str, nested: Nested, dialect: &dyn Dialect) -> Vec<String> {
+        let (schema, rows) = infer(input, nested);
+        let mut statements = schema.create_statements(dialect);
+        statements.extend(schema.insert_statements(&rows, dialect));
+        statements
+    }
+
+    #[test]
+    fn widens_integers_to_real_then_text() {
+        let (schema, _) = infer(r#"[{"a": 1}, {"a": 1.5}, {"a": "x"}]"#, Nested::Normalize);
+        assert_eq!(column(&schema, "t", "a").ty, Some(ColumnType::Text));
+        assert!(matches!(
+            schema.warnings.as_slice(),
+            [
+                Error::ColumnWidened {
+                    from: ColumnType::Integer,
+                    to: ColumnType::Real,
+                    row: 2,
+                    found: ColumnType::Real,
+                    ..
+                },
+                Error::ColumnWidened {
+                    from: ColumnType::Real,
+                    to: ColumnType::Text,
+                    row: 3,
+                    found: ColumnType::Text,
+                    ..
+                },
+            ]
+        ));
+        assert_eq!(
+            schema.warnings[1].to_string(),
+            "Column \"a\" in table \"t\" widened from real to text at row 3 (text found)"
+        );
+    }
+
+    #[test]
+    fn keeps_the_json_spelling_in_text_columns() {
+        let inserts = statements(
+            r#"[{"a": 1.0}, {"a": true}, {"a": "x"}, {"a": -Infinity}]"#,
+            Nested::Normalize,
+            &Sqlite,
+        );
+        assert_eq!(
+            inserts[1..],
+            [
+                r#"INSERT INTO "t" ("a") VALUES ('1.0');"#,
+                r#"INSERT INTO "t" ("a") VALUES ('true');"#,
+                r#"INSERT INTO "t" ("a") VALUES ('x');"#,
+                r#"INSERT INTO "t" ("a") VALUES ('-Infinity');"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_null_or_missing_somewhere_are_nullable() {
+        let (schema, _) = infer(
+            r#"[{"a": 1, "b": 1, "c": 1}, {"a": null, "c": 2}, {"a": 3, "c": 3, "d": 4}]"#,
+            Nested::Normalize,
+        );
+        let nullable = |name| column(&schema, "t", name).nullable;
+        assert!(nullable("a"));
+        assert!(nullable("b"));
+        assert!(!nullable("c"));
+        // Missing from the rows before it
+        assert!(nullable("d"));
+    }
+
+    #[test]
+    fn a_column_only_ever_null_is_text() {
+        let create = statements(r#"{"a": null}"#, Nested::Normalize, &Postgres);
+        assert_eq!(create[0], "CREATE TABLE \"t\" (\n    \"a\" TEXT\n);");
+    }
+
+    #[test]
+    fn normalizes_objects_into_referenced_tables() {
+        let (schema, rows) = infer(
+            r#"[{"id": 1, "user": {"name": "x"}}, {"id": 2, "user": null}]"#,
+            Nested::Normalize,
+        );
+        let names: Vec<_> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["t", "t_user"]);
+        assert!(schema.tables[1].keyed());
+        let link = column(&schema, "t", "user_id");
+        assert_eq!(link.references, Some(1));
+        assert!(link.nullable);
+        // The child row comes before the row pointing at it
+        let order: Vec<_> = rows.iter().map(|r| (r.table, r.id)).collect();
+        assert_eq!(order, [(1, 1), (0, 1), (0, 2)]);
+
+        let statements = statements(r#"{"user": {"name": "x"}}"#, Nested::Normalize, &Sqlite);
+        assert_eq!(
+            statements,
+            [
+                "CREATE TABLE \"t_user\" (\n    \"_id\" INTEGER PRIMARY KEY AUTOINCREMENT,\n    \"name\" TEXT NOT NULL\n);",
+                "CREATE TABLE \"t\" (\n    \"user_id\" INTEGER NOT NULL,\n    FOREIGN KEY (\"user_id\") REFERENCES \"t_user\" (\"_id\")\n);",
+                "INSERT INTO \"t_user\" (\"_id\", \"name\") VALUES (1, 'x');",
+                "INSERT INTO \"t\" (\"user_id\") VALUES (1);",
+            ]
+        );
+    }
+
+    #[test]
+    fn array_elements_keep_their_position() {
+        let (schema, rows) = infer(
+            r#"{"tags": ["a", "b"], "items": [{"n": 1}, {"n": 2}]}"#,
+            Nested::Normalize,
+        );
+        let names: Vec<_> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["t", "t_tags", "t_items"]);
+        assert!(schema.tables[0].keyed());
+        assert_eq!(column(&schema, "t_tags", "parent_id").references, Some(0));
+        assert_eq!(
+            column(&schema, "t_tags", "value").ty,
+            Some(ColumnType::Text)
+        );
+        assert_eq!(
+            column(&schema, "t_items", "n").ty,
+            Some(ColumnType::Integer)
+        );
+
+        let elements: Vec<_> = rows
+            .iter()
+            .filter(|r| r.table != 0)
+            .map(|r| {
+                let value = r.values.get("value").or(r.values.get("n")).unwrap();
+                let (parent, position) = (&r.values["parent_id"], &r.values["position"]);
+                format!("{} {parent} {position} {value}", r.table)
+            })
+            .collect();
+        assert_eq!(
+            elements,
+            ["1 1 0 \"a\"", "1 1 1 \"b\"", "2 1 0 1", "2 1 1 2"]
+        );
+    }
+
+    #[test]
+    fn flattens_nested_keys() {
+        let separator = || Nested::Flatten("_".to_string());
+        let (schema, _) = infer(
+            r#"{"a": {"b": 1, "c": {"d": true}}, "e": [1]}"#,
+            separator(),
+        );
+        let names: Vec<_> = schema.tables[0]
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["a_b", "a_c_d", "e"]);
+        assert_eq!(column(&schema, "t", "e").ty, Some(ColumnType::Text));
+
+        // A null under a flattened key leaves no column of its own
+        let (schema, _) = infer(r#"[{"a": null}, {"a": {"b": 1}}]"#, separator());
+        let names: Vec<_> = schema.tables[0]
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["a_b"]);
+
+        let document = parse_with(r#"{"a": {"b": 1}, "a_b": 2}"#, &json5()).unwrap();
+        let result = Schema::new("t", separator()).add_document(&document.json);
+        assert!(matches!(
+            result,
+            Err(Error::FlattenedKeyCollision { key, .. }) if key == "a_b"
+        ));
+    }
+
+    #[test]
+    fn json_columns_use_each_dialects_type() {
+        let dialects: [(&dyn Dialect, &str); 4] = [
+            (&Sqlite, "\"a\" TEXT"),
+            (&Postgres, "\"a\" JSONB"),
+            (&MySql, "`a` JSON"),
+            (&SqlServer, "[a] NVARCHAR(MAX)"),
+        ];
+        for (dialect, column) in dialects {
+            let statements = statements(r#"{"a": {"b": [1, 2]}}"#, Nested::Json, dialect);
+            assert!(
+                statements[0].contains(&format!("{column} NOT NULL")),
+                "{}",
+                statements[0]
+            );
+            assert!(
+                statements[1].contains(r#"'{"b":[1,2]}'"#),
+                "{}",
+                statements[1]
+            );
+        }
+    }
+
+    #[test]
+    fn quotes_identifiers_and_literals_per_dialect() {
+        let input = r#"{"it's \"q\" [x] `y`": "a'b\\c", "flag": true, "n": Infinity}"#;
+        let dialects: [(&dyn Dialect, &str); 4] = [
+            (
+                &Sqlite,
+                r#"INSERT INTO "t" ("it's ""q"" [x] `y`", "flag", "n") VALUES ('a''b\c', 1, 9e999);"#,
+            ),
+            (
+                &Postgres,
+                r#"INSERT INTO "t" ("it's ""q"" [x] `y`", "flag", "n") VALUES ('a''b\c', TRUE, 'Infinity');"#,
+            ),
+            (
+                &MySql,
+                r#"INSERT INTO `t` (`it's "q" [x] ``y```, `flag`, `n`) VALUES ('a''b\\c', TRUE, NULL);"#,
+            ),
+            (
+                &SqlServer,
+                r#"INSERT INTO [t] ([it's "q" [x]] `y`], [flag], [n]) VALUES (N'a''b\c', 1, NULL);"#,
+            ),
+        ];
+        for (dialect, expected) in dialects {
+            let statements = statements(input, Nested::Normalize, dialect);
+            assert_eq!(statements[1], expected);
+        }
+    }
+
+    fn add(schema: &mut Schema, record: &str) -> Result<Vec<Row>> {
+        schema.add_record(&parse_with(record, &json5()).unwrap().json)
+    }
+
+    // Inserts of a record read after the schema was frozen, streamed
+    // after those of the record it was inferred from
+    fn stream(dialect: &dyn Dialect) -> Vec<String> {
+        let record = r#"{"a": {"x": 1}, "b": {"y": 1}}"#;
+        let mut schema = Schema::new("t", Nested::Normalize);
+        let first = add(&mut schema, record).unwrap();
+        schema.freeze();
+        let mut keys = ExplicitKeys::default();
+        let mut statements = schema.stream_insert_statements(&first, dialect, &mut keys);
+        let rows = add(&mut schema, record).unwrap();
+        statements.extend(schema.stream_insert_statements(&rows, dialect, &mut keys));
+        statements.extend(schema.finish_inserts(keys, dialect));
+        statements
+    }
+
+    #[test]
+    fn frozen_tables_reject_other_definitions() {
+        let mut schema = Schema::new("t", Nested::Normalize);
+        add(&mut schema, r#"{"a": 1, "b": null, "o": {"x": 1}}"#).unwrap();
+        schema.freeze();
+        assert!(schema.tables.iter().all(Table::frozen));
+
+        // Fits, and a first value in a column typed by nulls alone is text
+        add(&mut schema, r#"{"a": 2, "b": 1.5, "o": {"x": 2}}"#).unwrap();
+        assert_eq!(column(&schema, "t", "b").ty, Some(ColumnType::Text));
+
+        let changes = [
+            (
+                r#"{"a": 1.5, "o": {"x": 1}}"#,
+                "column \"a\" would widen from integer to real",
+            ),
+            (r#"{"a": 1, "c": 1, "o": {"x": 1}}"#, "column \"c\" is new"),
+            (
+                r#"{"a": null, "o": {"x": 1}}"#,
+                "column \"a\" would hold a null",
+            ),
+            (
+                r#"{"a": 1, "o": {"x": 1}, "p": {"y": 1}}"#,
+                "the table is new",
+            ),
+        ];
+        for (record, expected) in changes {
+            match add(&mut schema, record) {
+                Err(Error::SchemaChanged { change, .. }) => {
+                    assert_eq!(change, expected, "{record}")
+                }
+                result => panic!("{record} gave {result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn restarting_numbers_rows_from_one() {
+        let records = [r#"{"o": {"x": 1}}"#, r#"{"o": {"x": 2}}"#];
+        let ids = |schema: &mut Schema| -> Vec<(usize, i64)> {
+            records
+                .iter()
+                .flat_map(|record| add(schema, record).unwrap())
+                .map(|row| (row.table, row.id))
+                .collect()
+        };
+
+        // A sample keeps counting where inference stopped
+        let mut schema = Schema::new("t", Nested::Normalize);
+        ids(&mut schema);
+        schema.freeze();
+        assert_eq!(ids(&mut schema), [(1, 3), (0, 3), (1, 4), (0, 4)]);
+
+        // A second pass numbers the same records as the first
+        let mut schema = Schema::new("t", Nested::Normalize);
+        let first = ids(&mut schema);
+        schema.restart();
+        schema.freeze();
+        assert_eq!(ids(&mut schema), first);
+        assert_eq!(first, [(1, 1), (0, 1), (1, 2), (0, 2)]);
+    }
+
+    #[test]
+    fn sql_server_allows_explicit_keys_in_one_table_at_a_time() {
+        assert_eq!(
+            stream(&SqlServer),
+            [
+                "SET IDENTITY_INSERT [t_a] ON;",
+                "INSERT INTO [t_a] ([_id], [x]) VALUES (1, 1);",
+                "SET IDENTITY_INSERT [t_a] OFF;",
+                "SET IDENTITY_INSERT [t_b] ON;",
+                "INSERT INTO [t_b] ([_id], [y]) VALUES (1, 1);",
+                "INSERT INTO [t] ([a_id], [b_id]) VALUES (1, 1);",
+                "SET IDENTITY_INSERT [t_b] OFF;",
+                "SET IDENTITY_INSERT [t_a] ON;",
+                "INSERT INTO [t_a] ([_id], [x]) VALUES (2, 1);",
+                "SET IDENTITY_INSERT [t_a] OFF;",
+                "SET IDENTITY_INSERT [t_b] ON;",
+                "INSERT INTO [t_b] ([_id], [y]) VALUES (2, 1);",
+                "INSERT INTO [t] ([a_id], [b_id]) VALUES (2, 2);",
+                "SET IDENTITY_INSERT [t_b] OFF;",
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_moves_sequences_past_explicit_keys_once_done() {
+        let statements = stream(&Postgres);
+        assert_eq!(
+            statements[statements.len() - 2..],
+            [
+                r#"SELECT setval(pg_get_serial_sequence('"t_a"', '_id'), 2);"#,
+                r#"SELECT setval(pg_get_serial_sequence('"t_b"', '_id'), 2);"#,
+            ]
+        );
+        assert_eq!(statements.len(), 8);
+
+        // Nothing to open or close in SQLite
+        assert!(stream(&Sqlite).iter().all(|s| s.starts_with("INSERT")));
+    }
+}
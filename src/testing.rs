@@ -0,0 +1,35 @@
+// Helpers shared by the unit tests
+
+use crate::parser::ParseOptions;
+
+pub fn json5() -> ParseOptions {
+    ParseOptions {
+        json5: true,
+        ..ParseOptions::default()
+    }
+}
+
+// Hands out at most `size` bytes per read, to place read boundaries
+// anywhere in the input
+pub struct Chunked<'a> {
+    data: &'a [u8],
+    size: usize,
+}
+
+impl Chunked<'_> {
+    pub fn new(data: &str, size: usize) -> Chunked<'_> {
+        Chunked {
+            data: data.as_bytes(),
+            size,
+        }
+    }
+}
+
+impl std::io::Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.size.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}